@@ -0,0 +1,153 @@
+// Compares the imperative and functional sums of 09_functions_HOF.rs, and
+// times the recursive `List::len` of 03_custom_types_enums_linked-list.rs.
+//
+//     cargo bench --bench functions -- [--quick] [--save <file.csv|file.json>]... [--baseline <file.csv>]
+//
+// With --baseline, benchmarks more than 10% slower than in the saved run
+// are listed and the exit code is 1.
+
+use rust_by_example::bench::{black_box, Bencher, Report};
+use std::{env, process, time::Duration};
+
+// The two sums of 09_functions_HOF.rs, with the limit as a parameter.
+
+fn is_odd(n: u32) -> bool {
+    n % 2 == 1
+}
+
+fn imperative_sum(upper: u32) -> u32 {
+    let mut acc = 0;
+    for n in 0.. {
+        let n_squared = n * n;
+        if n_squared >= upper {
+            break;
+        } else if is_odd(n_squared) {
+            acc += n_squared;
+        }
+    }
+    acc
+}
+
+fn functional_sum(upper: u32) -> u32 {
+    (0..)
+        .map(|n| n * n)
+        .take_while(|&n_squared| n_squared < upper)
+        .filter(|&n_squared| is_odd(n_squared))
+        .sum()
+}
+
+// The list of 03_custom_types_enums_linked-list.rs, with a loop-based
+// `len` to compare the recursion with.
+
+// Only the shape of the list matters here, not its elements.
+#[allow(dead_code)]
+enum List {
+    Cons(u32, Box<List>),
+    Nil,
+}
+
+use List::{Cons, Nil};
+
+impl List {
+    fn new() -> List {
+        Nil
+    }
+
+    fn prepend(self, elem: u32) -> List {
+        Cons(elem, Box::new(self))
+    }
+
+    fn len(&self) -> u32 {
+        match self {
+            Cons(_, tail) => 1 + tail.len(),
+            Nil => 0,
+        }
+    }
+
+    fn len_iterative(&self) -> u32 {
+        let (mut len, mut node) = (0, self);
+        while let Cons(_, tail) = node {
+            len += 1;
+            node = tail;
+        }
+        len
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(2);
+}
+
+fn main() {
+    let mut bencher = Bencher::new();
+    let mut saves = Vec::new();
+    let mut baseline = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // Cargo passes --bench to every benchmark binary.
+            "--bench" => {}
+            "--quick" => {
+                bencher = bencher
+                    .warm_up(Duration::from_millis(20))
+                    .samples(10)
+                    .sample_time(Duration::from_millis(1))
+            }
+            "--save" => saves.push(args.next().unwrap_or_else(|| fail("--save needs a file"))),
+            "--baseline" => {
+                baseline = Some(
+                    args.next()
+                        .unwrap_or_else(|| fail("--baseline needs a file")),
+                )
+            }
+            _ => fail(&format!("unexpected argument `{}`", arg)),
+        }
+    }
+
+    // Both styles have to agree before their speed matters.
+    for upper in [1000, 1_000_000] {
+        assert_eq!(imperative_sum(upper), functional_sum(upper));
+    }
+
+    let mut report = Report::new();
+    for upper in [1000, 1_000_000] {
+        let group = format!("sum of odd squares below {}", upper);
+        report.bench(&bencher, &group, "imperative", || {
+            imperative_sum(black_box(upper))
+        });
+        report.bench(&bencher, &group, "functional", || {
+            functional_sum(black_box(upper))
+        });
+    }
+    for length in [10, 100, 1000] {
+        let list = (0..length).fold(List::new(), List::prepend);
+        assert_eq!(list.len(), list.len_iterative());
+        let group = format!("List::len of {} elements", length);
+        report.bench(&bencher, &group, "recursive", || black_box(&list).len());
+        report.bench(&bencher, &group, "iterative", || {
+            black_box(&list).len_iterative()
+        });
+    }
+    print!("{}", report);
+
+    for path in &saves {
+        if let Err(e) = report.save(path) {
+            fail(&format!("{}: {}", path, e));
+        }
+        println!("saved {}", path);
+    }
+
+    if let Some(path) = baseline {
+        let baseline = Report::load(&path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+        let regressions = report.regressions(&baseline, 0.1);
+        if !regressions.is_empty() {
+            println!("\nslower than {}:", path);
+            for regression in &regressions {
+                println!("  {}", regression);
+            }
+            process::exit(1);
+        }
+    }
+}
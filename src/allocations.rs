@@ -0,0 +1,189 @@
+//! Counting heap allocations.
+//!
+//! `box_stack_and_heap::test` in `19_std_library_types.rs` prints how many
+//! bytes values take on the stack, but not when they go to the heap.
+//! [`CountingAllocator`] forwards every request to the system allocator and
+//! counts it for the thread that made it, and [`measure`] reports what a
+//! closure allocated.
+//!
+//! A program installs the allocator with `#[global_allocator]`, and with
+//! the `count-allocations` feature this crate installs it for everything
+//! that links to it:
+//!
+//! ```text
+//! cargo run --features count-allocations --bin 19_std_library_types
+//! ```
+//!
+//! ```
+//! use rust_by_example::allocations::{self, CountingAllocator};
+//!
+//! # #[cfg(not(feature = "count-allocations"))]
+//! #[global_allocator]
+//! static ALLOCATOR: CountingAllocator = CountingAllocator;
+//!
+//! fn main() {
+//!     let (_, stats) = allocations::measure(|| Box::new(7));
+//!     assert_eq!((stats.allocations, stats.bytes_allocated), (1, 4));
+//! }
+//! ```
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    hint::black_box,
+    ops::Sub,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Allocation counts of one thread.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub allocations: u64,
+    pub deallocations: u64,
+    /// Blocks grown or shrunk in place of a new allocation.
+    pub reallocations: u64,
+    pub bytes_allocated: u64,
+    pub bytes_deallocated: u64,
+}
+
+impl Stats {
+    const ZERO: Stats = Stats {
+        allocations: 0,
+        deallocations: 0,
+        reallocations: 0,
+        bytes_allocated: 0,
+        bytes_deallocated: 0,
+    };
+
+    /// How many more bytes were allocated than freed, which is negative if
+    /// more were freed.
+    pub fn net_bytes(&self) -> i64 {
+        self.bytes_allocated as i64 - self.bytes_deallocated as i64
+    }
+}
+
+/// The counts between two snapshots.
+impl Sub for Stats {
+    type Output = Stats;
+
+    fn sub(self, earlier: Stats) -> Stats {
+        Stats {
+            allocations: self.allocations - earlier.allocations,
+            deallocations: self.deallocations - earlier.deallocations,
+            reallocations: self.reallocations - earlier.reallocations,
+            bytes_allocated: self.bytes_allocated - earlier.bytes_allocated,
+            bytes_deallocated: self.bytes_deallocated - earlier.bytes_deallocated,
+        }
+    }
+}
+
+thread_local! {
+    // A `const` thread local needs no allocation of its own, which matters
+    // inside an allocator.
+    static COUNTS: Cell<Stats> = const { Cell::new(Stats::ZERO) };
+}
+
+// Set by the first allocation that goes through a `CountingAllocator`.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+fn record(update: impl FnOnce(&mut Stats)) {
+    // A load first keeps every allocation from writing to the same cache line.
+    if !INSTALLED.load(Ordering::Relaxed) {
+        INSTALLED.store(true, Ordering::Relaxed);
+    }
+    // A thread that is shutting down has no counts any more; its last
+    // deallocations go uncounted.
+    let _ = COUNTS.try_with(|counts| {
+        let mut stats = counts.get();
+        update(&mut stats);
+        counts.set(stats);
+    });
+}
+
+/// The system allocator, with counting. See the [module documentation](self).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CountingAllocator;
+
+// SAFETY: every method forwards to `System` with the arguments it was given,
+// so the blocks handed out are exactly the system allocator's. Counting does
+// not allocate, and does not unwind.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: the caller upholds `alloc`'s contract for `layout`.
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record(|stats| {
+                stats.allocations += 1;
+                stats.bytes_allocated += layout.size() as u64;
+            });
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: the caller upholds `alloc_zeroed`'s contract for `layout`.
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record(|stats| {
+                stats.allocations += 1;
+                stats.bytes_allocated += layout.size() as u64;
+            });
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: `ptr` was allocated by `System` through this allocator,
+        // with this `layout`.
+        System.dealloc(ptr, layout);
+        record(|stats| {
+            stats.deallocations += 1;
+            stats.bytes_deallocated += layout.size() as u64;
+        });
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // SAFETY: `ptr` was allocated by `System` through this allocator,
+        // with this `layout`, and the caller checked `new_size`.
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            // The old block is gone and a new one is in use, wherever it is.
+            record(|stats| {
+                stats.reallocations += 1;
+                stats.bytes_deallocated += layout.size() as u64;
+                stats.bytes_allocated += new_size as u64;
+            });
+        }
+        new_ptr
+    }
+}
+
+/// Returns whether a [`CountingAllocator`] is the global allocator.
+pub fn is_counting() -> bool {
+    // Allocate once, in case nothing has yet.
+    drop(black_box(Box::new(0u8)));
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+/// The counts of the current thread since it started.
+pub fn stats() -> Stats {
+    COUNTS.with(Cell::get)
+}
+
+/// Runs `f` and returns its result, with what the current thread allocated
+/// and freed while it ran. Allocations of other threads are not included.
+///
+/// # Panics
+///
+/// Panics if no [`CountingAllocator`] is installed, rather than report
+/// that nothing was allocated.
+pub fn measure<R>(f: impl FnOnce() -> R) -> (R, Stats) {
+    assert!(
+        is_counting(),
+        "no CountingAllocator is installed; enable the count-allocations feature \
+         or install one with #[global_allocator]"
+    );
+    let before = stats();
+    let result = f();
+    (result, stats() - before)
+}
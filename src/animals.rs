@@ -0,0 +1,415 @@
+//! Animals behind trait objects.
+//!
+//! `trait Animal` in `16_traits_0.rs` has `fn new(..) -> Self`, which makes
+//! it impossible to use as `dyn Animal`. Here construction lives in the
+//! separate [`Species`] trait, so [`Animal`] stays object safe and a farm
+//! can be a `Vec<Box<dyn Animal>>` of different species. A [`Registry`]
+//! maps species names to constructors, so the farm can come from a config.
+//!
+//! ```
+//! use rust_by_example::animals::{talk_round, Animal, Registry};
+//!
+//! let farm = Registry::with_defaults().populate("sheep Dolly\ndog Rex\n")?;
+//! assert_eq!(talk_round(&farm), ["Dolly pauses briefly... baaaaah!", "Rex says woof!"]);
+//! # Ok::<(), rust_by_example::animals::RegistryError>(())
+//! ```
+
+use std::{collections::BTreeMap, error, fmt};
+
+pub mod lifecycle;
+
+use lifecycle::{Clock, Fleece, LogEntry, TransitionError, WoolState};
+
+/// Behavior shared by every animal. Object safe.
+pub trait Animal {
+    fn name(&self) -> &str;
+    fn noise(&self) -> &'static str;
+
+    /// The species name this animal was registered under.
+    fn species(&self) -> &'static str;
+
+    // Traits can provide default method definitions.
+    fn talk(&self) -> String {
+        format!("{} says {}", self.name(), self.noise())
+    }
+}
+
+/// Construction of a concrete animal, split off from [`Animal`] because a
+/// method returning `Self` cannot be called through `dyn Animal`.
+pub trait Species: Animal + Sized {
+    /// The name used for this species in configs.
+    const NAME: &'static str;
+
+    fn new(name: &str) -> Self;
+}
+
+#[derive(Debug, Clone)]
+pub struct Sheep {
+    name: String,
+    fleece: Fleece,
+}
+
+impl Sheep {
+    /// Whether the sheep has been sheared and its wool has not grown back.
+    pub fn is_naked(&self) -> bool {
+        self.fleece.state() != WoolState::Woolly
+    }
+
+    pub fn fleece(&self) -> &Fleece {
+        &self.fleece
+    }
+
+    /// Gives the sheep a haircut, which fails if it is not woolly.
+    pub fn shear(&mut self, clock: &Clock) -> Result<(), TransitionError> {
+        self.fleece.shear(clock)
+    }
+
+    /// Lets the wool grow back as far as `clock` allows, and returns the
+    /// transitions that happened.
+    pub fn regrow(&mut self, clock: &Clock) -> Vec<LogEntry> {
+        self.fleece.tick(clock)
+    }
+}
+
+impl Animal for Sheep {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn noise(&self) -> &'static str {
+        match self.is_naked() {
+            true => "baaaaah?",
+            false => "baaaaah!",
+        }
+    }
+
+    fn species(&self) -> &'static str {
+        Self::NAME
+    }
+
+    // Default trait methods can be overridden.
+    fn talk(&self) -> String {
+        format!("{} pauses briefly... {}", self.name, self.noise())
+    }
+}
+
+impl Species for Sheep {
+    const NAME: &'static str = "sheep";
+
+    fn new(name: &str) -> Self {
+        Sheep {
+            name: name.to_string(),
+            fleece: Fleece::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cow {
+    name: String,
+    litres: u32,
+}
+
+impl Cow {
+    /// A full cow gives this many litres of milk.
+    pub const CAPACITY: u32 = 20;
+
+    /// Milks the cow dry and returns how many litres that gave.
+    pub fn milk(&mut self) -> u32 {
+        std::mem::take(&mut self.litres)
+    }
+
+    /// Lets the cow graze, which refills it by `litres`.
+    pub fn graze(&mut self, litres: u32) {
+        self.litres = self.litres.saturating_add(litres).min(Self::CAPACITY);
+    }
+}
+
+impl Animal for Cow {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn noise(&self) -> &'static str {
+        match self.litres {
+            Self::CAPACITY => "MOOO!",
+            0 => "moo.",
+            _ => "moo",
+        }
+    }
+
+    fn species(&self) -> &'static str {
+        Self::NAME
+    }
+}
+
+impl Species for Cow {
+    const NAME: &'static str = "cow";
+
+    fn new(name: &str) -> Self {
+        Cow {
+            name: name.to_string(),
+            litres: Self::CAPACITY,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Dog {
+    name: String,
+    tricks: Vec<String>,
+}
+
+impl Dog {
+    pub fn teach(&mut self, trick: &str) {
+        self.tricks.push(trick.to_string());
+    }
+
+    pub fn tricks(&self) -> &[String] {
+        &self.tricks
+    }
+}
+
+impl Animal for Dog {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn noise(&self) -> &'static str {
+        "woof!"
+    }
+
+    fn species(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn talk(&self) -> String {
+        match self.tricks.last() {
+            Some(trick) => format!("{} does {} and says {}", self.name, trick, self.noise()),
+            None => format!("{} says {}", self.name, self.noise()),
+        }
+    }
+}
+
+impl Species for Dog {
+    const NAME: &'static str = "dog";
+
+    fn new(name: &str) -> Self {
+        Dog {
+            name: name.to_string(),
+            tricks: Vec::new(),
+        }
+    }
+}
+
+/// The errors that can occur while creating animals by species name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// A species nobody registered, with the config line that named it.
+    UnknownSpecies {
+        species: String,
+        line: Option<usize>,
+    },
+    /// A config line that is not `<species> <name>`.
+    Syntax { line: usize, text: String },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::UnknownSpecies { species, line } => {
+                if let Some(line) = line {
+                    write!(f, "line {}: ", line)?;
+                }
+                write!(f, "unknown species `{}`", species)
+            }
+            RegistryError::Syntax { line, text } => {
+                write!(
+                    f,
+                    "line {}: expected `<species> <name>`, got `{}`",
+                    line, text
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for RegistryError {}
+
+type Constructor = fn(&str) -> Box<dyn Animal>;
+
+fn construct<S: Species + 'static>(name: &str) -> Box<dyn Animal> {
+    Box::new(S::new(name))
+}
+
+/// Constructors for animals, by species name.
+#[derive(Default)]
+pub struct Registry {
+    constructors: BTreeMap<&'static str, Constructor>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    /// A registry that knows every species in this module.
+    pub fn with_defaults() -> Registry {
+        let mut registry = Registry::new();
+        registry.register::<Sheep>();
+        registry.register::<Cow>();
+        registry.register::<Dog>();
+        registry
+    }
+
+    /// Makes `S` available under [`Species::NAME`].
+    pub fn register<S: Species + 'static>(&mut self) {
+        self.constructors.insert(S::NAME, construct::<S>);
+    }
+
+    /// The registered species names, in alphabetical order.
+    pub fn species(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.constructors.keys().copied()
+    }
+
+    pub fn create(&self, species: &str, name: &str) -> Result<Box<dyn Animal>, RegistryError> {
+        self.constructors
+            .get(species)
+            .map(|construct| construct(name))
+            .ok_or_else(|| RegistryError::UnknownSpecies {
+                species: species.to_string(),
+                line: None,
+            })
+    }
+
+    /// Creates one animal per `<species> <name>` line of `config`. Blank
+    /// lines and lines starting with `#` are ignored.
+    pub fn populate(&self, config: &str) -> Result<Vec<Box<dyn Animal>>, RegistryError> {
+        let mut animals = Vec::new();
+        for (index, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (species, name) = line.split_once(' ').ok_or_else(|| RegistryError::Syntax {
+                line: index + 1,
+                text: line.to_string(),
+            })?;
+            let animal = self.create(species, name.trim()).map_err(|e| match e {
+                RegistryError::UnknownSpecies { species, .. } => RegistryError::UnknownSpecies {
+                    species,
+                    line: Some(index + 1),
+                },
+                e => e,
+            })?;
+            animals.push(animal);
+        }
+        Ok(animals)
+    }
+}
+
+/// Lets every animal talk once, in order.
+pub fn talk_round(animals: &[Box<dyn Animal>]) -> Vec<String> {
+    animals.iter().map(|animal| animal.talk()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_talk_round_over_mixed_species() {
+        let mut dolly = Sheep::new("Dolly");
+        dolly.shear(&Clock::new()).unwrap();
+        let mut daisy = Cow::new("Daisy");
+        daisy.milk();
+        let mut rex = Dog::new("Rex");
+        rex.teach("a roll");
+
+        let farm: Vec<Box<dyn Animal>> = vec![
+            Box::new(dolly),
+            Box::new(daisy),
+            Box::new(rex),
+            Box::new(Cow::new("Bella")),
+        ];
+        assert_eq!(
+            talk_round(&farm),
+            [
+                "Dolly pauses briefly... baaaaah?",
+                "Daisy says moo.",
+                "Rex does a roll and says woof!",
+                "Bella says MOOO!",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_species_state() {
+        let mut clock = Clock::new();
+        let mut dolly = Sheep::new("Dolly");
+        assert_eq!(dolly.shear(&clock), Ok(()));
+        assert!(dolly.shear(&clock).is_err());
+        assert!(dolly.is_naked());
+        clock.advance(Fleece::REST_DAYS + Fleece::REGROW_DAYS);
+        assert_eq!(dolly.regrow(&clock).len(), 2);
+        assert_eq!(dolly.noise(), "baaaaah!");
+        assert_eq!(dolly.fleece().log().len(), 3);
+
+        let mut daisy = Cow::new("Daisy");
+        assert_eq!(daisy.milk(), 20);
+        assert_eq!(daisy.milk(), 0);
+        daisy.graze(5);
+        assert_eq!(daisy.noise(), "moo");
+        daisy.graze(100);
+        assert_eq!(daisy.milk(), Cow::CAPACITY);
+        daisy.graze(1);
+        daisy.graze(u32::MAX);
+        assert_eq!(daisy.milk(), Cow::CAPACITY);
+    }
+
+    #[test]
+    fn test_registry_populates_from_config() {
+        let registry = Registry::with_defaults();
+        assert_eq!(
+            registry.species().collect::<Vec<_>>(),
+            ["cow", "dog", "sheep"]
+        );
+
+        let farm = registry
+            .populate("# the farm\nsheep Dolly\n\ncow Daisy the Second\n")
+            .unwrap();
+        assert_eq!(farm[1].name(), "Daisy the Second");
+        assert_eq!(farm[1].species(), "cow");
+        assert_eq!(
+            talk_round(&farm),
+            [
+                "Dolly pauses briefly... baaaaah!",
+                "Daisy the Second says MOOO!"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_registry_errors() {
+        let registry = Registry::with_defaults();
+        let error = registry.populate("sheep Dolly\nunicorn Sparkle\n").err();
+        assert_eq!(
+            error,
+            Some(RegistryError::UnknownSpecies {
+                species: "unicorn".to_string(),
+                line: Some(2)
+            })
+        );
+        assert_eq!(
+            error.unwrap().to_string(),
+            "line 2: unknown species `unicorn`"
+        );
+        let error = registry.populate("sheep\n").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 1: expected `<species> <name>`, got `sheep`"
+        );
+        let error = Registry::new().create("sheep", "Dolly").err().unwrap();
+        assert_eq!(error.to_string(), "unknown species `sheep`");
+    }
+}
@@ -0,0 +1,390 @@
+//! The wool of a sheep as an explicit state machine.
+//!
+//! `Sheep::shear` in `16_traits_0.rs` flips a `naked` flag and prints a
+//! complaint when there is nothing to shear. Here the fleece moves through
+//! [`WoolState::Woolly`], [`WoolState::Sheared`] and [`WoolState::Regrowing`]
+//! on a simulated [`Clock`], and every illegal move is a [`TransitionError`].
+//! Each transition is logged, and [`Fleece::replay`] rebuilds the state from
+//! a log. [`Sheep`](super::Sheep) keeps its wool in a [`Fleece`].
+//!
+//! ```
+//! use rust_by_example::animals::lifecycle::{Clock, Fleece, WoolState};
+//!
+//! let mut clock = Clock::new();
+//! let mut fleece = Fleece::new();
+//! fleece.shear(&clock)?;
+//! assert!(fleece.shear(&clock).is_err());
+//!
+//! clock.advance(Fleece::REST_DAYS + Fleece::REGROW_DAYS);
+//! fleece.tick(&clock);
+//! assert_eq!(fleece.state(), WoolState::Woolly);
+//! assert_eq!(Fleece::replay(fleece.log())?, fleece);
+//! # Ok::<(), rust_by_example::animals::lifecycle::TransitionError>(())
+//! ```
+
+use std::{error, fmt};
+
+/// Simulated time, counted in whole days.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Clock {
+    day: u64,
+}
+
+impl Clock {
+    pub fn new() -> Clock {
+        Clock::default()
+    }
+
+    pub fn now(&self) -> u64 {
+        self.day
+    }
+
+    /// Moves the clock on by `days`. It stops at the last day there is.
+    pub fn advance(&mut self, days: u64) {
+        self.day = self.day.saturating_add(days);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WoolState {
+    Woolly,
+    Sheared,
+    Regrowing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// `Woolly` to `Sheared`. Done by the farmer.
+    Shear,
+    /// `Sheared` to `Regrowing`, once the skin has rested.
+    StartRegrowing,
+    /// `Regrowing` to `Woolly`, once the wool has grown back.
+    FinishRegrowing,
+}
+
+impl Transition {
+    pub const ALL: [Transition; 3] = [
+        Transition::Shear,
+        Transition::StartRegrowing,
+        Transition::FinishRegrowing,
+    ];
+}
+
+/// Why a transition was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransitionError {
+    /// `transition` does not start from `from`.
+    Illegal {
+        from: WoolState,
+        transition: Transition,
+    },
+    /// `transition` is legal, but not before day `ready_at`.
+    TooEarly {
+        transition: Transition,
+        ready_at: u64,
+    },
+    /// The transition is dated before the previous one.
+    TimeWentBackwards { at: u64, last: u64 },
+    /// `transition` would only be ready after the last day a [`Clock`] has.
+    OutOfTime { transition: Transition },
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransitionError::Illegal { from, transition } => {
+                write!(f, "cannot {:?} while {:?}", transition, from)
+            }
+            TransitionError::TooEarly {
+                transition,
+                ready_at,
+            } => write!(f, "cannot {:?} before day {}", transition, ready_at),
+            TransitionError::TimeWentBackwards { at, last } => {
+                write!(
+                    f,
+                    "day {} is before the last transition on day {}",
+                    at, last
+                )
+            }
+            TransitionError::OutOfTime { transition } => {
+                write!(f, "cannot {:?} before the end of time", transition)
+            }
+        }
+    }
+}
+
+impl error::Error for TransitionError {}
+
+/// A transition and the day it happened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogEntry {
+    pub at: u64,
+    pub transition: Transition,
+}
+
+/// The wool of one sheep, with the history of how it got there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fleece {
+    state: WoolState,
+    since: u64,
+    log: Vec<LogEntry>,
+}
+
+impl Default for Fleece {
+    fn default() -> Self {
+        Fleece::new()
+    }
+}
+
+impl Fleece {
+    /// Days a sheared sheep rests before its wool starts growing again.
+    pub const REST_DAYS: u64 = 7;
+    /// Days the wool takes to grow back.
+    pub const REGROW_DAYS: u64 = 30;
+
+    /// A woolly fleece on day 0.
+    pub fn new() -> Fleece {
+        Fleece {
+            state: WoolState::Woolly,
+            since: 0,
+            log: Vec::new(),
+        }
+    }
+
+    pub fn state(&self) -> WoolState {
+        self.state
+    }
+
+    /// The day the current state was entered.
+    pub fn since(&self) -> u64 {
+        self.since
+    }
+
+    pub fn log(&self) -> &[LogEntry] {
+        &self.log
+    }
+
+    /// Performs `transition` on day `at`, or explains why it cannot happen.
+    pub fn apply(&mut self, transition: Transition, at: u64) -> Result<(), TransitionError> {
+        if let Some(last) = self.log.last() {
+            if at < last.at {
+                return Err(TransitionError::TimeWentBackwards { at, last: last.at });
+            }
+        }
+        let (next, delay) = match (self.state, transition) {
+            (WoolState::Woolly, Transition::Shear) => (WoolState::Sheared, 0),
+            (WoolState::Sheared, Transition::StartRegrowing) => {
+                (WoolState::Regrowing, Self::REST_DAYS)
+            }
+            (WoolState::Regrowing, Transition::FinishRegrowing) => {
+                (WoolState::Woolly, Self::REGROW_DAYS)
+            }
+            (from, transition) => return Err(TransitionError::Illegal { from, transition }),
+        };
+        let ready_at = self
+            .since
+            .checked_add(delay)
+            .ok_or(TransitionError::OutOfTime { transition })?;
+        if at < ready_at {
+            return Err(TransitionError::TooEarly {
+                transition,
+                ready_at,
+            });
+        }
+        self.state = next;
+        self.since = at;
+        self.log.push(LogEntry { at, transition });
+        Ok(())
+    }
+
+    /// Shears the sheep now.
+    pub fn shear(&mut self, clock: &Clock) -> Result<(), TransitionError> {
+        self.apply(Transition::Shear, clock.now())
+    }
+
+    /// Performs every transition that has become due by `clock`, dated on
+    /// the day it became due, and returns them.
+    pub fn tick(&mut self, clock: &Clock) -> Vec<LogEntry> {
+        let start = self.log.len();
+        loop {
+            let (transition, delay) = match self.state {
+                WoolState::Woolly => break,
+                WoolState::Sheared => (Transition::StartRegrowing, Self::REST_DAYS),
+                WoolState::Regrowing => (Transition::FinishRegrowing, Self::REGROW_DAYS),
+            };
+            // A transition due after the end of time never happens.
+            let Some(due) = self.since.checked_add(delay) else {
+                break;
+            };
+            if due > clock.now() || self.apply(transition, due).is_err() {
+                break;
+            }
+        }
+        self.log[start..].to_vec()
+    }
+
+    /// Rebuilds a fleece by applying every entry of `log` to a new one.
+    pub fn replay(log: &[LogEntry]) -> Result<Fleece, TransitionError> {
+        let mut fleece = Fleece::new();
+        for entry in log {
+            fleece.apply(entry.transition, entry.at)?;
+        }
+        Ok(fleece)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fleece_in(state: WoolState) -> Fleece {
+        let mut fleece = Fleece::new();
+        let path = match state {
+            WoolState::Woolly => &[][..],
+            WoolState::Sheared => &[Transition::Shear][..],
+            WoolState::Regrowing => &[Transition::Shear, Transition::StartRegrowing][..],
+        };
+        let mut day = 0;
+        for &transition in path {
+            if transition == Transition::StartRegrowing {
+                day += Fleece::REST_DAYS;
+            }
+            fleece.apply(transition, day).unwrap();
+        }
+        fleece
+    }
+
+    #[test]
+    fn test_every_edge() {
+        let legal = [
+            (WoolState::Woolly, Transition::Shear, WoolState::Sheared),
+            (
+                WoolState::Sheared,
+                Transition::StartRegrowing,
+                WoolState::Regrowing,
+            ),
+            (
+                WoolState::Regrowing,
+                Transition::FinishRegrowing,
+                WoolState::Woolly,
+            ),
+        ];
+        for from in [WoolState::Woolly, WoolState::Sheared, WoolState::Regrowing] {
+            for transition in Transition::ALL {
+                let mut fleece = fleece_in(from);
+                let late_enough = fleece.since() + 1000;
+                let result = fleece.apply(transition, late_enough);
+                match legal
+                    .iter()
+                    .find(|(f, t, _)| (*f, *t) == (from, transition))
+                {
+                    Some(&(_, _, to)) => {
+                        assert_eq!(result, Ok(()));
+                        assert_eq!(fleece.state(), to);
+                    }
+                    None => {
+                        assert_eq!(result, Err(TransitionError::Illegal { from, transition }));
+                        assert_eq!(fleece, fleece_in(from));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_timed_edges_wait() {
+        let mut fleece = fleece_in(WoolState::Sheared);
+        assert_eq!(
+            fleece.apply(Transition::StartRegrowing, Fleece::REST_DAYS - 1),
+            Err(TransitionError::TooEarly {
+                transition: Transition::StartRegrowing,
+                ready_at: Fleece::REST_DAYS
+            })
+        );
+        let mut fleece = fleece_in(WoolState::Regrowing);
+        let error = fleece.apply(Transition::FinishRegrowing, 10).unwrap_err();
+        assert_eq!(error.to_string(), "cannot FinishRegrowing before day 37");
+        assert_eq!(fleece.state(), WoolState::Regrowing);
+    }
+
+    #[test]
+    fn test_time_cannot_go_backwards() {
+        let mut fleece = Fleece::new();
+        fleece.apply(Transition::Shear, 50).unwrap();
+        assert_eq!(
+            fleece.apply(Transition::StartRegrowing, 40),
+            Err(TransitionError::TimeWentBackwards { at: 40, last: 50 })
+        );
+    }
+
+    #[test]
+    fn test_clock_drives_tick() {
+        let mut clock = Clock::new();
+        let mut fleece = Fleece::new();
+        assert!(fleece.tick(&clock).is_empty());
+        fleece.shear(&clock).unwrap();
+
+        clock.advance(3);
+        assert!(fleece.tick(&clock).is_empty());
+        clock.advance(100);
+        let fired = fleece.tick(&clock);
+        assert_eq!(
+            fired,
+            [
+                LogEntry {
+                    at: 7,
+                    transition: Transition::StartRegrowing
+                },
+                LogEntry {
+                    at: 37,
+                    transition: Transition::FinishRegrowing
+                },
+            ]
+        );
+        assert_eq!(fleece.state(), WoolState::Woolly);
+        fleece.shear(&clock).unwrap();
+        assert_eq!(fleece.since(), 103);
+    }
+
+    #[test]
+    fn test_the_end_of_time() {
+        let mut clock = Clock::new();
+        clock.advance(u64::MAX - 1);
+        clock.advance(10);
+        assert_eq!(clock.now(), u64::MAX);
+
+        let mut fleece = Fleece::new();
+        fleece.shear(&clock).unwrap();
+        assert!(fleece.tick(&clock).is_empty());
+        assert_eq!(
+            fleece.apply(Transition::StartRegrowing, u64::MAX),
+            Err(TransitionError::OutOfTime {
+                transition: Transition::StartRegrowing
+            })
+        );
+        assert_eq!(fleece.state(), WoolState::Sheared);
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut clock = Clock::new();
+        let mut fleece = Fleece::new();
+        for _ in 0..3 {
+            fleece.shear(&clock).unwrap();
+            clock.advance(40);
+            fleece.tick(&clock);
+        }
+        assert_eq!(fleece.log().len(), 9);
+        assert_eq!(Fleece::replay(fleece.log()), Ok(fleece.clone()));
+
+        let mut tampered = fleece.log().to_vec();
+        tampered.remove(1);
+        assert_eq!(
+            Fleece::replay(&tampered),
+            Err(TransitionError::Illegal {
+                from: WoolState::Sheared,
+                transition: Transition::FinishRegrowing
+            })
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Integer arithmetic that reports failure instead of panicking.
+//!
+//! [`div`](crate::div) from the documentation testing chapter panics on a
+//! zero divisor, and so does `i32::MIN / -1`, whose result does not fit in
+//! an `i32`. The `checked_*` functions here return a [`MathError`] for both,
+//! and for every other operation that leaves the range of `i32`.
+//!
+//! ```
+//! use rust_by_example::arithmetic::{self, MathError};
+//!
+//! assert_eq!(arithmetic::checked_div(10, 2), Ok(5));
+//! assert_eq!(arithmetic::checked_div(10, 0), Err(MathError::DivisionByZero));
+//! assert_eq!(arithmetic::checked_div(i32::MIN, -1), Err(MathError::Overflow));
+//! ```
+//!
+//! [`expr`] builds an expression evaluator on top of them.
+
+pub mod expr;
+
+use std::{error, fmt};
+
+/// Why an arithmetic operation has no `i32` result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathError {
+    /// The divisor of a division or remainder is zero.
+    DivisionByZero,
+    /// The result is out of the range of `i32`.
+    Overflow,
+    /// An integer power with a negative exponent, which is a fraction.
+    NegativeExponent,
+}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MathError::DivisionByZero => write!(f, "division by zero"),
+            MathError::Overflow => write!(f, "result does not fit in an i32"),
+            MathError::NegativeExponent => write!(f, "negative exponent"),
+        }
+    }
+}
+
+impl error::Error for MathError {}
+
+/// Returns `a + b`.
+pub fn checked_add(a: i32, b: i32) -> Result<i32, MathError> {
+    a.checked_add(b).ok_or(MathError::Overflow)
+}
+
+/// Returns `a - b`.
+pub fn checked_sub(a: i32, b: i32) -> Result<i32, MathError> {
+    a.checked_sub(b).ok_or(MathError::Overflow)
+}
+
+/// Returns `a * b`.
+pub fn checked_mul(a: i32, b: i32) -> Result<i32, MathError> {
+    a.checked_mul(b).ok_or(MathError::Overflow)
+}
+
+/// Returns `a / b`, rounded towards zero like `/`.
+pub fn checked_div(a: i32, b: i32) -> Result<i32, MathError> {
+    if b == 0 {
+        return Err(MathError::DivisionByZero);
+    }
+    a.checked_div(b).ok_or(MathError::Overflow)
+}
+
+/// Returns the remainder of `a / b`, which has the sign of `a` like `%`.
+///
+/// ```
+/// use rust_by_example::arithmetic::checked_rem;
+///
+/// assert_eq!(checked_rem(-7, 3), Ok(-1));
+/// // The remainder is 0, but `i32::MIN % -1` panics anyway.
+/// assert!(checked_rem(i32::MIN, -1).is_err());
+/// ```
+pub fn checked_rem(a: i32, b: i32) -> Result<i32, MathError> {
+    if b == 0 {
+        return Err(MathError::DivisionByZero);
+    }
+    a.checked_rem(b).ok_or(MathError::Overflow)
+}
+
+/// Returns `-a`.
+pub fn checked_neg(a: i32) -> Result<i32, MathError> {
+    a.checked_neg().ok_or(MathError::Overflow)
+}
+
+/// Returns `base` to the power of `exp`.
+///
+/// ```
+/// use rust_by_example::arithmetic::{checked_pow, MathError};
+///
+/// assert_eq!(checked_pow(-2, 3), Ok(-8));
+/// assert_eq!(checked_pow(2, -1), Err(MathError::NegativeExponent));
+/// ```
+pub fn checked_pow(base: i32, exp: i32) -> Result<i32, MathError> {
+    let exp = u32::try_from(exp).map_err(|_| MathError::NegativeExponent)?;
+    base.checked_pow(exp).ok_or(MathError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operations_agree_with_wide_arithmetic() {
+        let values = [
+            i32::MIN,
+            i32::MIN + 1,
+            -46341,
+            -7,
+            -1,
+            0,
+            1,
+            3,
+            46341,
+            i32::MAX,
+        ];
+        let fit = |n: i64| i32::try_from(n).map_err(|_| MathError::Overflow);
+        for a in values {
+            for b in values {
+                let (wa, wb) = (i64::from(a), i64::from(b));
+                assert_eq!(checked_add(a, b), fit(wa + wb));
+                assert_eq!(checked_sub(a, b), fit(wa - wb));
+                assert_eq!(checked_mul(a, b), fit(wa * wb));
+                if b == 0 {
+                    assert_eq!(checked_div(a, b), Err(MathError::DivisionByZero));
+                    assert_eq!(checked_rem(a, b), Err(MathError::DivisionByZero));
+                } else if (a, b) != (i32::MIN, -1) {
+                    assert_eq!(checked_div(a, b), fit(wa / wb));
+                    assert_eq!(checked_rem(a, b), fit(wa % wb));
+                }
+            }
+            assert_eq!(checked_neg(a), fit(-i64::from(a)));
+        }
+    }
+
+    #[test]
+    fn test_min_divided_by_minus_one_overflows() {
+        assert_eq!(checked_div(i32::MIN, -1), Err(MathError::Overflow));
+        assert_eq!(checked_rem(i32::MIN, -1), Err(MathError::Overflow));
+    }
+
+    #[test]
+    fn test_pow() {
+        assert_eq!(checked_pow(0, 0), Ok(1));
+        assert_eq!(checked_pow(-1, i32::MAX), Ok(-1));
+        assert_eq!(checked_pow(2, 30), Ok(1 << 30));
+        assert_eq!(checked_pow(2, 31), Err(MathError::Overflow));
+        assert_eq!(checked_pow(-2, 31), Ok(i32::MIN));
+        assert_eq!(checked_pow(0, -1), Err(MathError::NegativeExponent));
+    }
+}
@@ -0,0 +1,633 @@
+//! Parses and evaluates integer expressions like `2 * (x + 1) ^ 2`.
+//!
+//! The grammar, from loosest to tightest binding:
+//!
+//! ```text
+//! sum     = product (("+" | "-") product)*
+//! product = unary (("*" | "/" | "%") unary)*
+//! unary   = "-" unary | power
+//! power   = atom ("^" unary)?
+//! atom    = number | variable | "(" sum ")"
+//! ```
+//!
+//! So `^` groups to the right and binds tighter than unary minus: `-2 ^ 2`
+//! is `-4`, and `2 ^ 3 ^ 2` is `2 ^ 9`. Every value is an `i32`, and every
+//! operation is checked with the functions of [`arithmetic`](super). A
+//! minus sign right before `2147483648` makes it `i32::MIN`, which could not
+//! be typed otherwise.
+//!
+//! Errors carry the [`Span`] of the input they are about, which
+//! [`Error::underline`] points out:
+//!
+//! ```
+//! use rust_by_example::arithmetic::expr;
+//! use std::collections::HashMap;
+//!
+//! let vars = HashMap::from([("x".to_string(), 2)]);
+//! assert_eq!(expr::eval("2 * (x + 1) ^ 2", &vars), Ok(18));
+//!
+//! let error = expr::eval("1 + 10 / (x - 2)", &vars).unwrap_err();
+//! assert_eq!(error.to_string(), "division by zero");
+//! assert_eq!(error.underline("1 + 10 / (x - 2)"), "1 + 10 / (x - 2)\n    ^^^^^^^^^^^^");
+//! ```
+
+use super::{self as arithmetic, MathError};
+use std::{collections::HashMap, error, fmt, str::FromStr};
+
+/// How deeply an expression may nest. Parentheses, minus signs and
+/// exponents each add a level, and so does every operator of a chain like
+/// `1 + 2 + 3`. Parsing and [`Expr::eval`] recurse once per level.
+pub const MAX_DEPTH: usize = 256;
+
+/// A range of byte offsets into the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The span from the start of `self` to the end of `other`.
+    fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+        }
+    }
+}
+
+/// What went wrong while parsing or evaluating.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A character that starts no token.
+    UnexpectedChar(char),
+    /// A token where it makes no sense, such as the second of `1 2`.
+    UnexpectedToken(String),
+    /// The input ends where an operand is needed.
+    UnexpectedEnd,
+    /// A `(` without its `)`.
+    UnclosedParen,
+    /// A number literal out of the range of `i32`.
+    NumberTooLarge,
+    /// An expression nested more than [`MAX_DEPTH`] deep.
+    TooDeep,
+    /// A variable that has no value.
+    UnknownVariable(String),
+    /// An operation without a result.
+    Math(MathError),
+}
+
+/// An [`ErrorKind`] and where in the input it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub span: Span,
+}
+
+impl Error {
+    /// Returns `source`, the input that produced this error, with the
+    /// span of the error marked by `^`s on the line below.
+    pub fn underline(&self, source: &str) -> String {
+        // Columns count characters, not bytes.
+        let column = |offset: usize| source[..offset.min(source.len())].chars().count();
+        let (start, end) = (column(self.span.start), column(self.span.end));
+        format!(
+            "{}\n{}{}",
+            source,
+            " ".repeat(start),
+            "^".repeat((end - start).max(1))
+        )
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::UnexpectedChar(c) => write!(f, "unexpected character `{}`", c),
+            ErrorKind::UnexpectedToken(token) => write!(f, "unexpected `{}`", token),
+            ErrorKind::UnexpectedEnd => write!(f, "unexpected end of input"),
+            ErrorKind::UnclosedParen => write!(f, "unclosed parenthesis"),
+            ErrorKind::NumberTooLarge => write!(f, "number does not fit in an i32"),
+            ErrorKind::TooDeep => write!(f, "nested more than {} deep", MAX_DEPTH),
+            ErrorKind::UnknownVariable(name) => write!(f, "unknown variable `{}`", name),
+            ErrorKind::Math(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::Math(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A binary operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+impl BinOp {
+    fn from_char(c: char) -> Option<BinOp> {
+        match c {
+            '+' => Some(BinOp::Add),
+            '-' => Some(BinOp::Sub),
+            '*' => Some(BinOp::Mul),
+            '/' => Some(BinOp::Div),
+            '%' => Some(BinOp::Rem),
+            '^' => Some(BinOp::Pow),
+            _ => None,
+        }
+    }
+
+    /// Applies the operator to `a` and `b`.
+    pub fn apply(self, a: i32, b: i32) -> Result<i32, MathError> {
+        match self {
+            BinOp::Add => arithmetic::checked_add(a, b),
+            BinOp::Sub => arithmetic::checked_sub(a, b),
+            BinOp::Mul => arithmetic::checked_mul(a, b),
+            BinOp::Div => arithmetic::checked_div(a, b),
+            BinOp::Rem => arithmetic::checked_rem(a, b),
+            BinOp::Pow => arithmetic::checked_pow(a, b),
+        }
+    }
+}
+
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+            BinOp::Pow => "^",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// The shape of an [`Expr`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprKind {
+    Number(i32),
+    Variable(String),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+/// A parsed expression, and the span of the input it was parsed from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+    // The number of levels of the tree, at most `MAX_DEPTH`.
+    height: usize,
+}
+
+impl Expr {
+    // An expression of `kind`, unless it is nested too deeply.
+    fn new(kind: ExprKind, span: Span) -> Result<Expr, Error> {
+        let height = 1 + match &kind {
+            ExprKind::Number(_) | ExprKind::Variable(_) => 0,
+            ExprKind::Neg(operand) => operand.height,
+            ExprKind::Binary(_, left, right) => left.height.max(right.height),
+        };
+        if height > MAX_DEPTH {
+            return Err(Error {
+                kind: ErrorKind::TooDeep,
+                span,
+            });
+        }
+        Ok(Expr { kind, span, height })
+    }
+
+    /// Parses `source` as a whole.
+    pub fn parse(source: &str) -> Result<Expr, Error> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            end: source.len(),
+            depth: 0,
+        };
+        let expr = parser.sum()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(token.unexpected()),
+        }
+    }
+
+    /// Evaluates the expression, looking variables up in `vars`.
+    pub fn eval(&self, vars: &HashMap<String, i32>) -> Result<i32, Error> {
+        let math = |e| Error {
+            kind: ErrorKind::Math(e),
+            span: self.span,
+        };
+        match &self.kind {
+            ExprKind::Number(n) => Ok(*n),
+            ExprKind::Variable(name) => vars.get(name).copied().ok_or_else(|| Error {
+                kind: ErrorKind::UnknownVariable(name.clone()),
+                span: self.span,
+            }),
+            ExprKind::Neg(operand) => arithmetic::checked_neg(operand.eval(vars)?).map_err(math),
+            ExprKind::Binary(op, left, right) => {
+                op.apply(left.eval(vars)?, right.eval(vars)?).map_err(math)
+            }
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Expr, Error> {
+        Expr::parse(s)
+    }
+}
+
+/// Parses and evaluates `source` in one go.
+pub fn eval(source: &str, vars: &HashMap<String, i32>) -> Result<i32, Error> {
+    Expr::parse(source)?.eval(vars)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    // Wider than `i32`, so that `-2147483648` can be `i32::MIN`.
+    Number(i64),
+    Ident(String),
+    Op(BinOp),
+    Open,
+    Close,
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    span: Span,
+}
+
+impl Token {
+    fn unexpected(&self) -> Error {
+        let text = match &self.kind {
+            TokenKind::Number(n) => n.to_string(),
+            TokenKind::Ident(name) => name.clone(),
+            TokenKind::Op(op) => op.to_string(),
+            TokenKind::Open => "(".to_string(),
+            TokenKind::Close => ")".to_string(),
+        };
+        Error {
+            kind: ErrorKind::UnexpectedToken(text),
+            span: self.span,
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        // The end of the token that starts with `c`, extended below for
+        // numbers and names.
+        let mut end = start + c.len_utf8();
+        let mut take_while = |accept: fn(char) -> bool| {
+            while let Some(&(i, c)) = chars.peek() {
+                if !accept(c) {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            end
+        };
+        let kind = if c.is_whitespace() {
+            continue;
+        } else if c.is_ascii_digit() {
+            let end = take_while(|c| c.is_ascii_digit());
+            match source[start..end].parse() {
+                Ok(n) => TokenKind::Number(n),
+                Err(_) => {
+                    return Err(Error {
+                        kind: ErrorKind::NumberTooLarge,
+                        span: Span { start, end },
+                    })
+                }
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let end = take_while(|c| c.is_alphanumeric() || c == '_');
+            TokenKind::Ident(source[start..end].to_string())
+        } else if c == '(' {
+            TokenKind::Open
+        } else if c == ')' {
+            TokenKind::Close
+        } else if let Some(op) = BinOp::from_char(c) {
+            TokenKind::Op(op)
+        } else {
+            return Err(Error {
+                kind: ErrorKind::UnexpectedChar(c),
+                span: Span { start, end },
+            });
+        };
+        tokens.push(Token {
+            kind,
+            span: Span { start, end },
+        });
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    // The length of the input, where `UnexpectedEnd` points.
+    end: usize,
+    // How many parentheses, minus signs and exponents enclose the position.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    // Consumes the next token if it is one of `ops`.
+    fn next_op(&mut self, ops: &[BinOp]) -> Option<BinOp> {
+        match self.peek()?.kind {
+            TokenKind::Op(op) if ops.contains(&op) => {
+                self.position += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    // Goes one level deeper at `span`. Errors end the parse, so only the
+    // callers that succeed need to come back up.
+    fn nest(&mut self, span: Span) -> Result<(), Error> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(Error {
+                kind: ErrorKind::TooDeep,
+                span,
+            });
+        }
+        Ok(())
+    }
+
+    // Whether the next token is the literal `2147483648`, which only makes
+    // sense negated, and not the base of a power.
+    fn at_negated_min(&self) -> bool {
+        let literal = matches!(
+            self.peek().map(|token| &token.kind),
+            Some(TokenKind::Number(n)) if *n == -i64::from(i32::MIN)
+        );
+        let power = matches!(
+            self.tokens.get(self.position + 1).map(|token| &token.kind),
+            Some(TokenKind::Op(BinOp::Pow))
+        );
+        literal && !power
+    }
+
+    fn binary(op: BinOp, left: Expr, right: Expr) -> Result<Expr, Error> {
+        let span = left.span.to(right.span);
+        Expr::new(ExprKind::Binary(op, Box::new(left), Box::new(right)), span)
+    }
+
+    fn sum(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.product()?;
+        while let Some(op) = self.next_op(&[BinOp::Add, BinOp::Sub]) {
+            expr = Parser::binary(op, expr, self.product()?)?;
+        }
+        Ok(expr)
+    }
+
+    fn product(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.unary()?;
+        while let Some(op) = self.next_op(&[BinOp::Mul, BinOp::Div, BinOp::Rem]) {
+            expr = Parser::binary(op, expr, self.unary()?)?;
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        // A loop rather than recursion, so that `------1` needs no stack.
+        let mut minuses = Vec::new();
+        while let Some(minus) = self.peek().map(|token| token.span) {
+            if self.next_op(&[BinOp::Sub]).is_none() {
+                break;
+            }
+            self.nest(minus)?;
+            minuses.push(minus);
+        }
+        let depth = minuses.len();
+        let mut expr = match minuses.last() {
+            Some(&minus) if self.at_negated_min() => {
+                let literal = self.tokens[self.position].span;
+                self.position += 1;
+                minuses.pop();
+                Expr::new(ExprKind::Number(i32::MIN), minus.to(literal))?
+            }
+            _ => self.power()?,
+        };
+        for minus in minuses.into_iter().rev() {
+            let span = minus.to(expr.span);
+            expr = Expr::new(ExprKind::Neg(Box::new(expr)), span)?;
+        }
+        self.depth -= depth;
+        Ok(expr)
+    }
+
+    fn power(&mut self) -> Result<Expr, Error> {
+        let base = self.atom()?;
+        let Some(caret) = self.peek().map(|token| token.span) else {
+            return Ok(base);
+        };
+        if self.next_op(&[BinOp::Pow]).is_none() {
+            return Ok(base);
+        }
+        self.nest(caret)?;
+        // The exponent may be negative, as in `2 ^ -1`, which fails later.
+        let exponent = self.unary()?;
+        self.depth -= 1;
+        Parser::binary(BinOp::Pow, base, exponent)
+    }
+
+    fn atom(&mut self) -> Result<Expr, Error> {
+        let Some(token) = self.tokens.get(self.position) else {
+            return Err(Error {
+                kind: ErrorKind::UnexpectedEnd,
+                span: Span {
+                    start: self.end,
+                    end: self.end,
+                },
+            });
+        };
+        let span = token.span;
+        let kind = match &token.kind {
+            TokenKind::Number(n) => match i32::try_from(*n) {
+                Ok(n) => ExprKind::Number(n),
+                Err(_) => {
+                    return Err(Error {
+                        kind: ErrorKind::NumberTooLarge,
+                        span,
+                    })
+                }
+            },
+            TokenKind::Ident(name) => ExprKind::Variable(name.clone()),
+            TokenKind::Open => {
+                self.position += 1;
+                self.nest(span)?;
+                let inner = self.sum()?;
+                self.depth -= 1;
+                return match self.peek() {
+                    Some(Token {
+                        kind: TokenKind::Close,
+                        span: close,
+                    }) => {
+                        let close = *close;
+                        self.position += 1;
+                        Ok(Expr {
+                            span: span.to(close),
+                            ..inner
+                        })
+                    }
+                    Some(token) => Err(token.unexpected()),
+                    None => Err(Error {
+                        kind: ErrorKind::UnclosedParen,
+                        span,
+                    }),
+                };
+            }
+            TokenKind::Op(_) | TokenKind::Close => return Err(token.unexpected()),
+        };
+        self.position += 1;
+        Expr::new(kind, span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calc(source: &str) -> Result<i32, Error> {
+        let vars = HashMap::from([("x".to_string(), 3), ("big".to_string(), i32::MAX)]);
+        eval(source, &vars)
+    }
+
+    // The error of `source`, and the part of `source` it points at.
+    fn error(source: &str) -> (ErrorKind, &str) {
+        let error = calc(source).unwrap_err();
+        (error.kind, &source[error.span.start..error.span.end])
+    }
+
+    #[test]
+    fn test_precedence_and_associativity() {
+        assert_eq!(calc("1 + 2 * 3"), Ok(7));
+        assert_eq!(calc("(1 + 2) * 3"), Ok(9));
+        assert_eq!(calc("10 - 4 - 3"), Ok(3));
+        assert_eq!(calc("100 / 10 / 5"), Ok(2));
+        assert_eq!(calc("2 ^ 3 ^ 2"), Ok(512));
+        assert_eq!(calc("-2 ^ 2"), Ok(-4));
+        assert_eq!(calc("(-2) ^ 2"), Ok(4));
+        assert_eq!(calc("2 * -x"), Ok(-6));
+        assert_eq!(calc("--x"), Ok(3));
+        assert_eq!(calc("-7 % 3 + x * x"), Ok(8));
+    }
+
+    #[test]
+    fn test_parse_errors_point_at_the_problem() {
+        assert_eq!(error("1 + $"), (ErrorKind::UnexpectedChar('$'), "$"));
+        assert_eq!(error("1 2"), (ErrorKind::UnexpectedToken("2".into()), "2"));
+        assert_eq!(
+            error("(1 + 2))"),
+            (ErrorKind::UnexpectedToken(")".into()), ")")
+        );
+        assert_eq!(
+            error("1 * / 2"),
+            (ErrorKind::UnexpectedToken("/".into()), "/")
+        );
+        assert_eq!(error("1 +"), (ErrorKind::UnexpectedEnd, ""));
+        assert_eq!(error("2 * (1 + x"), (ErrorKind::UnclosedParen, "("));
+        assert_eq!(
+            error("99999999999"),
+            (ErrorKind::NumberTooLarge, "99999999999")
+        );
+        assert_eq!(calc("1 +").unwrap_err().span, Span { start: 3, end: 3 });
+    }
+
+    #[test]
+    fn test_i32_min_literal() {
+        assert_eq!(calc("-2147483648"), Ok(i32::MIN));
+        assert_eq!(calc("-2147483648 + x - 3"), Ok(i32::MIN));
+        assert_eq!(
+            error("--2147483648"),
+            (ErrorKind::Math(MathError::Overflow), "--2147483648")
+        );
+        assert_eq!(
+            error("2147483648"),
+            (ErrorKind::NumberTooLarge, "2147483648")
+        );
+        // `^` binds tighter, so the literal is not negated first.
+        assert_eq!(
+            error("-2147483648 ^ 1"),
+            (ErrorKind::NumberTooLarge, "2147483648")
+        );
+    }
+
+    #[test]
+    fn test_deep_nesting_is_an_error() {
+        let limit = |prefix: &str, suffix: &str, depth: usize| {
+            format!("{}x{}", prefix.repeat(depth), suffix.repeat(depth))
+        };
+        assert_eq!(calc(&limit("-", "", MAX_DEPTH - 1)), Ok(-3));
+        assert_eq!(calc(&limit("(", ")", MAX_DEPTH - 1)), Ok(3));
+        assert_eq!(calc(&limit("1 + ", "", MAX_DEPTH - 1)), Ok(258));
+        for (prefix, suffix) in [("-", ""), ("(", ")"), ("1 + ", ""), ("x ^ ", "")] {
+            let source = limit(prefix, suffix, 100_000);
+            let error = calc(&source).unwrap_err();
+            assert_eq!(error.kind, ErrorKind::TooDeep, "{}", prefix);
+            assert_eq!(error.to_string(), "nested more than 256 deep");
+        }
+    }
+
+    #[test]
+    fn test_eval_errors_point_at_the_operation() {
+        assert_eq!(
+            error("1 + y * 2"),
+            (ErrorKind::UnknownVariable("y".into()), "y")
+        );
+        assert_eq!(
+            error("1 + 6 / (x - 3)"),
+            (ErrorKind::Math(MathError::DivisionByZero), "6 / (x - 3)")
+        );
+        assert_eq!(
+            error("(big + 1) * 0"),
+            (ErrorKind::Math(MathError::Overflow), "(big + 1)")
+        );
+        assert_eq!(
+            error("-(-big - 1)"),
+            (ErrorKind::Math(MathError::Overflow), "-(-big - 1)")
+        );
+        assert_eq!(
+            error("x ^ -1"),
+            (ErrorKind::Math(MathError::NegativeExponent), "x ^ -1")
+        );
+    }
+
+    #[test]
+    fn test_underline_counts_characters() {
+        let source = "\u{e9}t\u{e9} + 1";
+        let error = calc(source).unwrap_err();
+        assert_eq!(error.to_string(), "unknown variable `\u{e9}t\u{e9}`");
+        assert_eq!(error.underline(source), format!("{}\n^^^", source));
+        let end = calc("1 -").unwrap_err();
+        assert_eq!(end.underline("1 -"), "1 -\n   ^");
+    }
+}
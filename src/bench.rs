@@ -0,0 +1,618 @@
+//! Micro-benchmarks without a benchmarking crate.
+//!
+//! `09_functions_HOF.rs` says that its imperative loop and its iterator
+//! chain compute the same thing, but not whether one is faster. A
+//! [`Bencher`] times a closure: it warms up first, then takes a number of
+//! samples, each timing enough calls to rise above the clock's
+//! resolution. Results are collected in a [`Report`], which prints as a
+//! table of medians and 95th percentiles, saves as CSV or JSON, and
+//! compares itself with an earlier CSV run to spot regressions.
+//!
+//! ```
+//! use rust_by_example::bench::{black_box, Bencher, Report};
+//! use std::time::Duration;
+//!
+//! let bencher = Bencher::new()
+//!     .warm_up(Duration::from_millis(1))
+//!     .samples(5)
+//!     .sample_time(Duration::from_micros(100));
+//! let mut report = Report::new();
+//! report.bench(&bencher, "sum", "loop", || {
+//!     let mut sum = 0;
+//!     for n in 0..black_box(100u32) {
+//!         sum += n;
+//!     }
+//!     sum
+//! });
+//! report.bench(&bencher, "sum", "iterator", || (0..black_box(100u32)).sum::<u32>());
+//! assert_eq!(report.measurements().len(), 2);
+//! assert!(report.to_csv().starts_with("group,name,iterations,samples,"));
+//! ```
+
+use std::{
+    error, fmt, fs, io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// Hides a value from the optimizer, so that a benchmark is not optimized
+/// away. Re-exported for benchmarks to use on their inputs.
+pub use std::hint::black_box;
+
+/// Times closures. See the [module documentation](self).
+#[derive(Debug, Clone, Copy)]
+pub struct Bencher {
+    warm_up: Duration,
+    samples: usize,
+    sample_time: Duration,
+}
+
+impl Default for Bencher {
+    fn default() -> Bencher {
+        Bencher {
+            warm_up: Duration::from_millis(200),
+            samples: 50,
+            sample_time: Duration::from_millis(5),
+        }
+    }
+}
+
+// Calls `f` `iterations` times and returns how long that took.
+fn time<R>(f: &mut impl FnMut() -> R, iterations: u64) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(f());
+    }
+    start.elapsed()
+}
+
+impl Bencher {
+    /// 200 ms of warm-up, then 50 samples of about 5 ms each.
+    pub fn new() -> Bencher {
+        Bencher::default()
+    }
+
+    /// How long to run the closure before measuring, to fill caches and
+    /// to estimate how long one call takes.
+    pub fn warm_up(mut self, warm_up: Duration) -> Bencher {
+        self.warm_up = warm_up;
+        self
+    }
+
+    /// How many timings to take. At least one is always taken.
+    pub fn samples(mut self, samples: usize) -> Bencher {
+        self.samples = samples.max(1);
+        self
+    }
+
+    /// About how long each timing should take.
+    pub fn sample_time(mut self, sample_time: Duration) -> Bencher {
+        self.sample_time = sample_time;
+        self
+    }
+
+    /// Measures how long one call of `f` takes.
+    pub fn run<R>(&self, group: &str, name: &str, mut f: impl FnMut() -> R) -> Measurement {
+        // Doubling the batch size finds a useful one quickly, even for
+        // closures that take nanoseconds.
+        let (mut calls, mut elapsed, mut batch) = (0u64, Duration::ZERO, 1u64);
+        while elapsed < self.warm_up || calls == 0 {
+            elapsed += time(&mut f, batch);
+            calls += batch;
+            batch = batch.saturating_mul(2);
+        }
+        let per_call = elapsed.as_nanos() as f64 / calls as f64;
+        let iterations = ((self.sample_time.as_nanos() as f64 / per_call.max(1.0)) as u64).max(1);
+
+        let samples = (0..self.samples)
+            .map(|_| time(&mut f, iterations).as_nanos() as f64 / iterations as f64)
+            .collect();
+        Measurement::from_samples(group, name, iterations, samples)
+    }
+}
+
+/// The timings of one benchmark, in nanoseconds per call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    /// The comparison the benchmark belongs to.
+    pub group: String,
+    pub name: String,
+    /// How many calls each sample timed.
+    pub iterations: u64,
+    pub samples: usize,
+    pub median_ns: f64,
+    /// 95% of the samples took at most this long.
+    pub p95_ns: f64,
+    pub min_ns: f64,
+    pub mean_ns: f64,
+}
+
+// The value below which `fraction` of the sorted `values` lie, by the
+// nearest-rank method.
+fn percentile(sorted: &[f64], fraction: f64) -> f64 {
+    let rank = (fraction * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+impl Measurement {
+    /// Summarizes `samples`, the nanoseconds per call of each sample.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no samples.
+    pub fn from_samples(
+        group: &str,
+        name: &str,
+        iterations: u64,
+        mut samples: Vec<f64>,
+    ) -> Measurement {
+        assert!(!samples.is_empty(), "a measurement needs samples");
+        samples.sort_by(f64::total_cmp);
+        let n = samples.len();
+        let median = if n % 2 == 1 {
+            samples[n / 2]
+        } else {
+            (samples[n / 2 - 1] + samples[n / 2]) / 2.0
+        };
+        Measurement {
+            group: group.to_string(),
+            name: name.to_string(),
+            iterations,
+            samples: n,
+            median_ns: median,
+            p95_ns: percentile(&samples, 0.95),
+            min_ns: samples[0],
+            mean_ns: samples.iter().sum::<f64>() / n as f64,
+        }
+    }
+}
+
+/// Formats nanoseconds with a unit that keeps the number short.
+///
+/// ```
+/// use rust_by_example::bench::format_ns;
+///
+/// assert_eq!(format_ns(12.34), "12.3 ns");
+/// assert_eq!(format_ns(4_560.0), "4.56 µs");
+/// assert_eq!(format_ns(2.5e9), "2.50 s");
+/// ```
+pub fn format_ns(ns: f64) -> String {
+    let (value, unit) = match ns {
+        ns if ns < 1e3 => (ns, "ns"),
+        ns if ns < 1e6 => (ns / 1e3, "µs"),
+        ns if ns < 1e9 => (ns / 1e6, "ms"),
+        ns => (ns / 1e9, "s"),
+    };
+    // Three significant digits.
+    let decimals = if value < 10.0 {
+        2
+    } else if value < 100.0 {
+        1
+    } else {
+        0
+    };
+    format!("{:.*} {}", decimals, value, unit)
+}
+
+/// Why a saved report could not be read or written.
+#[derive(Debug)]
+pub enum ReportError {
+    Io(io::Error),
+    /// A file whose extension is neither `.csv` nor `.json`, or one that
+    /// is not `.csv` when loading.
+    UnknownFormat(String),
+    /// A line of a CSV report that does not parse.
+    Syntax {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportError::Io(e) => write!(f, "could not access report: {}", e),
+            ReportError::UnknownFormat(path) => {
+                write!(
+                    f,
+                    "{}: reports are saved as .csv or .json, and loaded from .csv",
+                    path
+                )
+            }
+            ReportError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl error::Error for ReportError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ReportError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReportError {
+    fn from(e: io::Error) -> Self {
+        ReportError::Io(e)
+    }
+}
+
+const CSV_HEADER: &str = "group,name,iterations,samples,median_ns,p95_ns,min_ns,mean_ns";
+
+// Quotes a CSV field if it needs it.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+// Splits a CSV line into fields, undoing `csv_field`.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().unwrap();
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    fields
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// A benchmark that got slower than in a baseline run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub group: String,
+    pub name: String,
+    /// The median of the baseline.
+    pub before_ns: f64,
+    pub after_ns: f64,
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} / {}: {} -> {} (+{:.0}%)",
+            self.group,
+            self.name,
+            format_ns(self.before_ns),
+            format_ns(self.after_ns),
+            (self.after_ns / self.before_ns - 1.0) * 100.0
+        )
+    }
+}
+
+/// Measurements, grouped into comparisons.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    measurements: Vec<Measurement>,
+}
+
+impl Report {
+    pub fn new() -> Report {
+        Report::default()
+    }
+
+    pub fn measurements(&self) -> &[Measurement] {
+        &self.measurements
+    }
+
+    pub fn push(&mut self, measurement: Measurement) {
+        self.measurements.push(measurement);
+    }
+
+    /// Runs `f` with `bencher` and adds the measurement. The first
+    /// benchmark of a group is the one the others are compared with.
+    pub fn bench<R>(&mut self, bencher: &Bencher, group: &str, name: &str, f: impl FnMut() -> R) {
+        self.push(bencher.run(group, name, f));
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", CSV_HEADER);
+        for m in &self.measurements {
+            csv.push_str(&format!(
+                "{},{},{},{},{:.3},{:.3},{:.3},{:.3}\n",
+                csv_field(&m.group),
+                csv_field(&m.name),
+                m.iterations,
+                m.samples,
+                m.median_ns,
+                m.p95_ns,
+                m.min_ns,
+                m.mean_ns
+            ));
+        }
+        csv
+    }
+
+    /// Reads a report written by [`to_csv`](Report::to_csv).
+    pub fn from_csv(csv: &str) -> Result<Report, ReportError> {
+        let mut lines = csv.lines().enumerate().filter(|(_, line)| !line.is_empty());
+        match lines.next() {
+            Some((_, header)) if header == CSV_HEADER => {}
+            _ => {
+                return Err(ReportError::Syntax {
+                    line: 1,
+                    message: format!("expected the header `{}`", CSV_HEADER),
+                })
+            }
+        }
+        let mut report = Report::new();
+        for (index, line) in lines {
+            let syntax = |message: String| ReportError::Syntax {
+                line: index + 1,
+                message,
+            };
+            let fields = csv_fields(line);
+            let [group, name, iterations, samples, median, p95, min, mean] = &fields[..] else {
+                return Err(syntax(format!("expected 8 fields, found {}", fields.len())));
+            };
+            let number = |field: &str| {
+                field
+                    .parse::<f64>()
+                    .map_err(|_| syntax(format!("`{}` is not a number", field)))
+            };
+            let not_a_count = |field: &str| syntax(format!("`{}` is not a count", field));
+            report.push(Measurement {
+                group: group.clone(),
+                name: name.clone(),
+                iterations: iterations.parse().map_err(|_| not_a_count(iterations))?,
+                samples: samples.parse().map_err(|_| not_a_count(samples))?,
+                median_ns: number(median)?,
+                p95_ns: number(p95)?,
+                min_ns: number(min)?,
+                mean_ns: number(mean)?,
+            });
+        }
+        Ok(report)
+    }
+
+    pub fn to_json(&self) -> String {
+        let objects: Vec<String> = self
+            .measurements
+            .iter()
+            .map(|m| {
+                format!(
+                    "  {{\"group\": {}, \"name\": {}, \"iterations\": {}, \"samples\": {}, \
+                     \"median_ns\": {:.3}, \"p95_ns\": {:.3}, \"min_ns\": {:.3}, \"mean_ns\": {:.3}}}",
+                    json_string(&m.group),
+                    json_string(&m.name),
+                    m.iterations,
+                    m.samples,
+                    m.median_ns,
+                    m.p95_ns,
+                    m.min_ns,
+                    m.mean_ns
+                )
+            })
+            .collect();
+        format!("[\n{}\n]\n", objects.join(",\n"))
+    }
+
+    /// Writes the report to `path`, as CSV or JSON by its extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReportError> {
+        let path = path.as_ref();
+        let contents = match path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => self.to_csv(),
+            Some("json") => self.to_json(),
+            _ => return Err(ReportError::UnknownFormat(path.display().to_string())),
+        };
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Reads a report saved as CSV.
+    pub fn load(path: impl AsRef<Path>) -> Result<Report, ReportError> {
+        let path = path.as_ref();
+        if path.extension().and_then(|e| e.to_str()) != Some("csv") {
+            return Err(ReportError::UnknownFormat(path.display().to_string()));
+        }
+        Report::from_csv(&fs::read_to_string(path)?)
+    }
+
+    /// The benchmarks whose median is more than `tolerance` (0.1 for 10%)
+    /// above the one of the same benchmark in `baseline`. Benchmarks
+    /// missing from either report are not compared.
+    pub fn regressions(&self, baseline: &Report, tolerance: f64) -> Vec<Regression> {
+        self.measurements
+            .iter()
+            .filter_map(|m| {
+                let before = baseline
+                    .measurements
+                    .iter()
+                    .find(|b| b.group == m.group && b.name == m.name)?;
+                (m.median_ns > before.median_ns * (1.0 + tolerance)).then(|| Regression {
+                    group: m.group.clone(),
+                    name: m.name.clone(),
+                    before_ns: before.median_ns,
+                    after_ns: m.median_ns,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A table per group, with each median relative to the first benchmark
+/// of the group.
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .measurements
+            .iter()
+            .map(|m| m.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+        let mut groups: Vec<&str> = Vec::new();
+        for m in &self.measurements {
+            if !groups.contains(&m.group.as_str()) {
+                groups.push(&m.group);
+            }
+        }
+        for (i, group) in groups.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}", group)?;
+            writeln!(
+                f,
+                "  {:<width$}  {:>10}  {:>10}  {:>10}  {:>7}",
+                "name", "median", "p95", "min", "ratio"
+            )?;
+            let members: Vec<&Measurement> = self
+                .measurements
+                .iter()
+                .filter(|m| m.group == *group)
+                .collect();
+            for m in &members {
+                writeln!(
+                    f,
+                    "  {:<width$}  {:>10}  {:>10}  {:>10}  {:>6.2}x",
+                    m.name,
+                    format_ns(m.median_ns),
+                    format_ns(m.p95_ns),
+                    format_ns(m.min_ns),
+                    m.median_ns / members[0].median_ns
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        let mut report = Report::new();
+        report.push(Measurement::from_samples(
+            "sum",
+            "loop",
+            10,
+            vec![4.0, 2.0, 3.0, 1.0],
+        ));
+        report.push(Measurement::from_samples(
+            "sum",
+            "iterator, \"lazy\"",
+            10,
+            vec![6.0],
+        ));
+        report.push(Measurement::from_samples(
+            "len",
+            "recursive",
+            1,
+            (1..=100).map(f64::from).collect(),
+        ));
+        report
+    }
+
+    #[test]
+    fn test_statistics() {
+        let report = report();
+        let m = &report.measurements()[0];
+        assert_eq!(
+            (m.median_ns, m.p95_ns, m.min_ns, m.mean_ns),
+            (2.5, 4.0, 1.0, 2.5)
+        );
+        let m = &report.measurements()[2];
+        assert_eq!((m.median_ns, m.p95_ns, m.samples), (50.5, 95.0, 100));
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(
+            report().to_string(),
+            "sum
+  name                  median         p95         min    ratio
+  loop                 2.50 ns     4.00 ns     1.00 ns    1.00x
+  iterator, \"lazy\"     6.00 ns     6.00 ns     6.00 ns    2.40x
+
+len
+  name                  median         p95         min    ratio
+  recursive            50.5 ns     95.0 ns     1.00 ns    1.00x
+"
+        );
+    }
+
+    #[test]
+    fn test_csv_round_trip_and_json() {
+        let report = report();
+        let csv = report.to_csv();
+        assert!(csv.contains("\nsum,\"iterator, \"\"lazy\"\"\",10,1,6.000,"));
+        assert_eq!(Report::from_csv(&csv).unwrap(), report);
+        assert!(matches!(
+            Report::from_csv(&format!("{}\nsum,loop,1\n", CSV_HEADER)),
+            Err(ReportError::Syntax { line: 2, .. })
+        ));
+        for counts in ["1.5,1", "-3,1", "10,2.0", "10,-1"] {
+            let csv = format!("{}\nsum,loop,{},6,6,6,6\n", CSV_HEADER, counts);
+            let error = Report::from_csv(&csv).unwrap_err();
+            assert!(error.to_string().ends_with("is not a count"), "{}", error);
+        }
+        assert!(matches!(
+            Report::load("baseline.json"),
+            Err(ReportError::UnknownFormat(_))
+        ));
+
+        let json = report.to_json();
+        assert!(
+            json.starts_with("[\n  {\"group\": \"sum\", \"name\": \"loop\", \"iterations\": 10,")
+        );
+        assert!(json.contains("\"name\": \"iterator, \\\"lazy\\\"\""));
+    }
+
+    #[test]
+    fn test_regressions() {
+        let before = report();
+        let mut after = Report::new();
+        after.push(Measurement::from_samples("sum", "loop", 10, vec![2.6]));
+        after.push(Measurement::from_samples("len", "recursive", 1, vec![60.0]));
+        after.push(Measurement::from_samples("len", "new", 1, vec![600.0]));
+        let regressions = after.regressions(&before, 0.1);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(
+            regressions[0].to_string(),
+            "len / recursive: 50.5 ns -> 60.0 ns (+19%)"
+        );
+    }
+
+    #[test]
+    fn test_bencher_takes_the_samples() {
+        let bencher = Bencher::new()
+            .warm_up(Duration::ZERO)
+            .samples(3)
+            .sample_time(Duration::from_micros(50));
+        let mut calls = 0u64;
+        let m = bencher.run("g", "count", || calls += 1);
+        assert_eq!(m.samples, 3);
+        // At least one call to warm up, then the samples.
+        assert!(calls > 3 * m.iterations);
+    }
+}
@@ -23,4 +23,22 @@ fn main() {
     use Color::*;
     println!("roses are #{:06x}", Red as i32);
     println!("violets are #{:06x}", Blue as i32);
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_by_example::layout::{self, TypeLayout};
+
+    // `layout` reports on copies of these types, which must not drift.
+    #[test]
+    fn test_layout_matches_the_library() {
+        assert_eq!(
+            TypeLayout::of::<super::Number>("Number"),
+            TypeLayout::of::<layout::Number>("Number")
+        );
+        assert_eq!(
+            TypeLayout::of::<super::Color>("Color"),
+            TypeLayout::of::<layout::Color>("Color")
+        );
+    }
+}
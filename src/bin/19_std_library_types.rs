@@ -75,6 +75,42 @@ mod box_stack_and_heap {
             "Unboxed point occupies {} bytes on the stack",
             mem::size_of_val(&unboxed_point)
         );
+
+        // With `--features count-allocations`, count what goes to the heap
+        #[cfg(feature = "count-allocations")]
+        {
+            use rust_by_example::allocations::measure;
+
+            let (_, stats) = measure(origin);
+            println!("Allocations by origin(): {}", stats.allocations);
+            let (_, stats) = measure(boxed_origin);
+            println!(
+                "Allocations by boxed_origin(): {} ({} bytes)",
+                stats.allocations, stats.bytes_allocated
+            );
+            let (_, stats) = measure(|| Box::new(boxed_origin()));
+            println!("Allocations by a boxed box: {}", stats.allocations);
+            let (_, stats) = measure(|| *boxed_point);
+            println!("Allocations by unboxing a point: {}", stats.allocations);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use rust_by_example::layout::{self, TypeLayout};
+
+        // `layout` reports on copies of these types, which must not drift.
+        #[test]
+        fn test_layout_matches_the_library() {
+            assert_eq!(
+                TypeLayout::of::<super::Point>("Point"),
+                TypeLayout::of::<layout::Point>("Point")
+            );
+            assert_eq!(
+                TypeLayout::of::<super::Rectangle>("Rectangle"),
+                TypeLayout::of::<layout::Rectangle>("Rectangle")
+            );
+        }
     }
 }
 
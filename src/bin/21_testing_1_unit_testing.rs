@@ -31,6 +31,10 @@ pub fn divide_non_zero_result(a: u32, b: u32) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rust_by_example::{
+        assert_approx_eq,
+        testing::{assert_panics, panic_message, property::assert_property},
+    };
 
     #[test]
     fn test_add() {
@@ -45,11 +49,24 @@ mod tests {
     #[test]
     // Unit tests can return Result<()>, which lets you use ? in them
     fn test_sqrt() -> Result<(), String> {
-        let x = 4.0;
-        assert_eq!(sqrt(x)?.powf(2.0), x);
+        // Squaring the root rounds twice, so only perfect squares such as 4
+        // come back exactly.
+        for x in [4.0, 2.0, 0.1, 1e-300, 12345.678] {
+            assert_approx_eq!(sqrt(x)?.powf(2.0), x);
+        }
+        assert_approx_eq!(sqrt(2.0)?, std::f64::consts::SQRT_2, ulps = 0);
+        assert_approx_eq!(sqrt(0.0)?, 0.0, abs = 0.0);
         Ok(())
     }
 
+    #[test]
+    fn test_sqrt_of_negative() {
+        assert_eq!(
+            sqrt(-1.0),
+            Err("negative floats don't have square roots".to_owned())
+        );
+    }
+
     #[test]
     fn test_divide() {
         assert_eq!(divide_non_zero_result(10, 2), 5);
@@ -67,12 +84,47 @@ mod tests {
         divide_non_zero_result(1, 10);
     }
 
+    #[test]
+    // Unlike `should_panic`, one test can check several panics this way
+    fn test_divide_panics() {
+        assert_panics(|| divide_non_zero_result(1, 0), "Divide-by-zero");
+        assert_panics(|| divide_non_zero_result(0, 0), "Divide-by-zero");
+        assert_panics(|| divide_non_zero_result(1, 10), "result is zero");
+        assert_eq!(divide_non_zero_result(10, 10), 1);
+    }
+
     #[test]
     fn test_add_hundred() {
         assert_eq!(add(100, 2), 102);
         assert_eq!(add(2, 100), 102);
     }
 
+    #[test]
+    // Properties hold for every input, not just the ones written down
+    fn test_add_is_commutative() {
+        // `i16`s, because the sum of two large `i32`s overflows.
+        assert_property(|(a, b): (i16, i16)| {
+            let (a, b) = (i32::from(a), i32::from(b));
+            add(a, b) == add(b, a) && add(a, 0) == a
+        });
+    }
+
+    #[test]
+    fn test_divide_non_zero_result_properties() {
+        // It panics exactly when the quotient would be zero...
+        assert_property(|(a, b): (u32, u32)| {
+            panic_message(|| divide_non_zero_result(a, b)).is_some() == (b == 0 || a < b)
+        });
+        // ...and otherwise returns a quotient of at least 1 that leaves a
+        // remainder smaller than `b`.
+        assert_property(|(a, b): (u32, u32)| {
+            let b = b.max(1);
+            let a = a.max(b);
+            let q = divide_non_zero_result(a, b);
+            q >= 1 && u64::from(q) * u64::from(b) <= u64::from(a) && a - q * b < b
+        });
+    }
+
     #[test]
     #[ignore]
     fn ignored_test() {
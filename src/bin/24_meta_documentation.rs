@@ -1,35 +1,10 @@
-/// A human being is represented here
-pub struct Person {
-    /// A person must have a name, no matter how much Juliet may hate it
-    name: String,
-}
-
-impl Person {
-    /// Creates a person with the given name.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// // You can have rust code between fences inside the comments
-    /// // If you pass --test to `rustdoc`, it will even test it for you!
-    /// use 24_meta_documentation::Person;
-    /// let person = Person::new("name");
-    /// ```
-    pub fn new(name: &str) -> Person {
-        Person {
-            name: name.to_string(),
-        }
-    }
-
-    /// Gives a friendly hello!
-    ///
-    /// Says "Hello, [name](Person::name)" to the `Person` it is called on.
-    pub fn hello(&self) {
-        println!("Hello, {}!", self.name);
-    }
-}
+// `Person` and its documentation now live in the library, in
+// `src/contacts.rs`. Doctests only run for library crates, and only there can
+// they name the crate they test: `use rust_by_example::contacts::Person;`.
+// Run them with `cargo test --doc`.
+use rust_by_example::contacts::Person;
 
 fn main() {
-    let john = Person::new("John");
+    let john = Person::new("John").expect("John is a valid name");
     john.hello();
 }
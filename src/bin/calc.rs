@@ -0,0 +1,92 @@
+// A calculator for integer expressions, one per line. `name = <expr>`
+// stores a value in a variable, and `ans` always holds the last result.
+
+use rust_by_example::arithmetic::expr::{self, Span};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, IsTerminal, Write},
+    process,
+};
+
+fn help() {
+    println!(
+        "Enter an expression with + - * / % ^, parentheses, numbers and variables.
+    <name> = <expr>  Store the value of <expr> in <name>.
+    vars             List the variables.
+    help             Show this text.
+    quit             Leave; so does the end of input."
+    );
+}
+
+// Splits `name = rest` into the name and the offset of `rest` in `line`.
+fn assignment(line: &str) -> Option<(&str, usize)> {
+    let (name, _) = line.split_once('=')?;
+    let name = name.trim();
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+    valid.then(|| (name, line.find('=').unwrap() + 1))
+}
+
+fn main() {
+    let interactive = io::stdin().is_terminal();
+    let prompt = || {
+        if interactive {
+            print!("> ");
+            io::stdout().flush().unwrap();
+        }
+    };
+    let mut vars = HashMap::new();
+
+    prompt();
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            // The line has been read all the same, so the next one is fine.
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                eprintln!("error: the line is not valid UTF-8");
+                prompt();
+                continue;
+            }
+            Err(e) => {
+                eprintln!("error: could not read input: {}", e);
+                process::exit(1);
+            }
+        };
+        match line.trim() {
+            "" => {}
+            "help" => help(),
+            "quit" => return,
+            "vars" => {
+                let mut names: Vec<_> = vars.iter().collect();
+                names.sort();
+                for (name, value) in names {
+                    println!("{} = {}", name, value);
+                }
+            }
+            _ => {
+                let (name, offset) = assignment(&line).unwrap_or(("ans", 0));
+                match expr::eval(&line[offset..], &vars) {
+                    Ok(value) => {
+                        if name == "ans" {
+                            println!("{}", value);
+                        } else {
+                            println!("{} = {}", name, value);
+                        }
+                        vars.insert(name.to_string(), value);
+                        vars.insert("ans".to_string(), value);
+                    }
+                    Err(mut e) => {
+                        // Point into the whole line, not just the expression.
+                        e.span = Span {
+                            start: e.span.start + offset,
+                            end: e.span.end + offset,
+                        };
+                        eprintln!("{}\nerror: {}", e.underline(&line), e);
+                    }
+                }
+            }
+        }
+        prompt();
+    }
+}
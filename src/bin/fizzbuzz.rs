@@ -0,0 +1,82 @@
+// Prints or counts fizzbuzz output for a range of numbers, using the classic
+// rules of `while_loop` in 08_flow_of_control.rs or a rules file such as
+// data/fizzbuzz.txt.
+
+use rust_by_example::fizzbuzz::Rules;
+use std::{collections::BTreeMap, env, process};
+
+fn help() {
+    println!(
+        "usage:
+    fizzbuzz [--rules <file>] [--count] [<from> <to>]
+        Print the output for every number from <from> to <to> (default 1 to 100).
+        --rules <file>  Read rules from <file> instead of using fizz/buzz.
+        --count         Print how often each word occurs instead of the words."
+    );
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    help();
+    process::exit(2);
+}
+
+fn main() {
+    let mut rules_path = None;
+    let mut count = false;
+    let mut bounds = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rules" => match args.next() {
+                Some(path) => rules_path = Some(path),
+                None => fail("--rules needs a file name"),
+            },
+            "--count" => count = true,
+            "-h" | "--help" => {
+                help();
+                return;
+            }
+            _ => match arg.parse::<i64>() {
+                Ok(n) => bounds.push(n),
+                Err(_) => fail(&format!("unexpected argument `{}`", arg)),
+            },
+        }
+    }
+
+    let (from, to) = match bounds[..] {
+        [] => (1, 100),
+        [from, to] => (from, to),
+        _ => fail("expected both <from> and <to>"),
+    };
+
+    let rules = match rules_path {
+        Some(path) => Rules::load(&path).unwrap_or_else(|e| {
+            eprintln!("error: {}: {}", path, e);
+            process::exit(1);
+        }),
+        None => Rules::classic(),
+    };
+
+    if !count {
+        for word in rules.apply(from..=to) {
+            println!("{}", word);
+        }
+        return;
+    }
+
+    let mut numbers = 0;
+    let mut words = BTreeMap::new();
+    for (n, word) in (from..=to).zip(rules.apply(from..=to)) {
+        if word == n.to_string() {
+            numbers += 1;
+        } else {
+            *words.entry(word).or_insert(0) += 1;
+        }
+    }
+    for (word, times) in &words {
+        println!("{}: {}", word, times);
+    }
+    println!("numbers: {}", numbers);
+}
@@ -0,0 +1,12 @@
+// Prints the size, alignment, padding and `Option` size of the types used
+// in the examples, such as `Point` and `Box<Point>` of
+// 19_std_library_types.rs and the enums of 03_custom_types_enums.rs.
+
+use rust_by_example::layout::Registry;
+
+fn main() {
+    print!("{}", Registry::examples());
+    println!();
+    println!("padding: bytes of the type that belong to no field");
+    println!("(niche): None fits into a bit pattern that no value uses");
+}
@@ -0,0 +1,386 @@
+//! Explicit numeric conversions with a chosen overflow policy.
+//!
+//! `as` silently picks a policy for you: integers are truncated to their
+//! least significant bits, floats saturate at the bounds of the target
+//! integer type and NaN becomes 0 (see `casting()` in `05_types.rs`). The
+//! helpers in this module make that choice visible at the call site:
+//!
+//! * [`checked_cast`] returns `None` whenever `as` would lose information
+//!   by overflowing, saturating or rounding,
+//! * [`saturating_cast`] clamps to the closest representable value,
+//! * [`wrapping_cast`] keeps the low bits, like `as` does for integers and
+//!   like `to_int_unchecked` typically does for in-range floats.
+//!
+//! ```
+//! use rust_by_example::cast::{checked_cast, saturating_cast, wrapping_cast};
+//!
+//! assert_eq!(checked_cast::<i32, u8>(1000), None);
+//! assert_eq!(saturating_cast::<i32, u8>(1000), 255);
+//! assert_eq!(wrapping_cast::<i32, u8>(1000), 232);
+//! ```
+
+/// Conversion from `S` into `Self` under the three overflow policies.
+///
+/// Implemented for every pair of primitive integer and floating point
+/// types, from `char` into every integer type and from `u8` into `char`.
+pub trait CastFrom<S>: Sized {
+    /// Converts `src`, or returns `None` if the value does not fit exactly.
+    ///
+    /// Float to integer conversions truncate towards zero first, so only
+    /// values outside the target range (and NaN) are rejected.
+    fn checked_cast_from(src: S) -> Option<Self>;
+
+    /// Converts `src`, clamping it to the bounds of `Self`. Float to integer
+    /// conversions turn NaN into 0; float to float ones keep NaN and
+    /// infinities.
+    fn saturating_cast_from(src: S) -> Self;
+
+    /// Converts `src`, keeping its value modulo `2^BITS` for integer targets.
+    /// Float to integer conversions turn NaN and infinities into 0.
+    fn wrapping_cast_from(src: S) -> Self;
+}
+
+/// Converts `src` into `T`, or returns `None` if it does not fit exactly.
+///
+/// ```
+/// use rust_by_example::cast::checked_cast;
+///
+/// assert_eq!(checked_cast::<i32, i16>(128), Some(128));
+/// assert_eq!(checked_cast::<i32, i8>(128), None);
+/// assert_eq!(checked_cast::<f32, u8>(f32::NAN), None);
+/// ```
+pub fn checked_cast<S, T: CastFrom<S>>(src: S) -> Option<T> {
+    T::checked_cast_from(src)
+}
+
+/// Converts `src` into `T`, clamping it to the bounds of `T`.
+///
+/// ```
+/// use rust_by_example::cast::saturating_cast;
+///
+/// assert_eq!(saturating_cast::<f32, u8>(300.0), 255);
+/// assert_eq!(saturating_cast::<i8, u8>(-1), 0);
+/// ```
+pub fn saturating_cast<S, T: CastFrom<S>>(src: S) -> T {
+    T::saturating_cast_from(src)
+}
+
+/// Converts `src` into `T`, wrapping around on overflow.
+///
+/// ```
+/// use rust_by_example::cast::wrapping_cast;
+///
+/// assert_eq!(wrapping_cast::<i8, u8>(-1), 255);
+/// assert_eq!(wrapping_cast::<f32, u8>(-100.0), 156);
+/// ```
+pub fn wrapping_cast<S, T: CastFrom<S>>(src: S) -> T {
+    T::wrapping_cast_from(src)
+}
+
+// 2^128, the modulus of the widest integer type. Exactly representable.
+const TWO_POW_128: f64 = 340_282_366_920_938_463_463_374_607_431_768_211_456.0;
+
+// Reduces a float modulo 2^128, returning the low 128 bits of its integer
+// part. Every narrower integer type can take its own low bits from this.
+fn float_low_bits(src: f64) -> u128 {
+    if !src.is_finite() {
+        return 0;
+    }
+    // `%` on floats is exact, and the remainder is an integer below 2^128.
+    let magnitude = (src.trunc().abs() % TWO_POW_128) as u128;
+    if src < 0.0 {
+        magnitude.wrapping_neg()
+    } else {
+        magnitude
+    }
+}
+
+macro_rules! impl_int_from_int {
+    ($src:ty => $($dst:ty),*) => {$(
+        impl CastFrom<$src> for $dst {
+            fn checked_cast_from(src: $src) -> Option<Self> {
+                <$dst>::try_from(src).ok()
+            }
+
+            #[allow(unused_comparisons)]
+            fn saturating_cast_from(src: $src) -> Self {
+                match <$dst>::try_from(src) {
+                    Ok(value) => value,
+                    Err(_) if src < 0 => <$dst>::MIN,
+                    Err(_) => <$dst>::MAX,
+                }
+            }
+
+            fn wrapping_cast_from(src: $src) -> Self {
+                src as $dst
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_float_from_int {
+    ($src:ty => $($dst:ty),*) => {$(
+        impl CastFrom<$src> for $dst {
+            fn checked_cast_from(src: $src) -> Option<Self> {
+                let value = src as $dst;
+                // Rounding shows up as a failed round trip. The round trip of
+                // an infinity saturates, so it has to be ruled out separately.
+                (value.is_finite() && value as $src == src).then_some(value)
+            }
+
+            fn saturating_cast_from(src: $src) -> Self {
+                // Only `u128 as f32` can round up to infinity.
+                (src as $dst).min(<$dst>::MAX)
+            }
+
+            fn wrapping_cast_from(src: $src) -> Self {
+                src as $dst
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_int_from_float {
+    ($src:ty => $($dst:ty),*) => {$(
+        impl CastFrom<$src> for $dst {
+            fn checked_cast_from(src: $src) -> Option<Self> {
+                // Both bounds are powers of two (or zero), so they convert to
+                // floats exactly, unlike `MAX` itself.
+                let lower = <$dst>::MIN as $src;
+                let upper = (<$dst>::MAX / 2 + 1) as $src * 2.0;
+                let truncated = src.trunc();
+                (truncated >= lower && truncated < upper).then_some(src as $dst)
+            }
+
+            fn saturating_cast_from(src: $src) -> Self {
+                src as $dst
+            }
+
+            fn wrapping_cast_from(src: $src) -> Self {
+                float_low_bits(src as f64) as $dst
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_float_from_float {
+    ($src:ty => $($dst:ty),*) => {$(
+        impl CastFrom<$src> for $dst {
+            fn checked_cast_from(src: $src) -> Option<Self> {
+                let value = src as $dst;
+                (src.is_nan() || value as $src == src).then_some(value)
+            }
+
+            fn saturating_cast_from(src: $src) -> Self {
+                let value = src as $dst;
+                // Only a finite value that overflowed while narrowing is
+                // clamped; infinities and NaN stay what they are.
+                if src.is_finite() {
+                    value.clamp(<$dst>::MIN, <$dst>::MAX)
+                } else {
+                    value
+                }
+            }
+
+            fn wrapping_cast_from(src: $src) -> Self {
+                src as $dst
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_int_from_char {
+    ($($dst:ty),*) => {$(
+        impl CastFrom<char> for $dst {
+            fn checked_cast_from(src: char) -> Option<Self> {
+                <$dst>::checked_cast_from(src as u32)
+            }
+
+            fn saturating_cast_from(src: char) -> Self {
+                <$dst>::saturating_cast_from(src as u32)
+            }
+
+            fn wrapping_cast_from(src: char) -> Self {
+                src as $dst
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_for_all_ints {
+    ($impl:ident: $($src:ty),*) => {$(
+        $impl!($src => i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+    )*};
+}
+
+impl_for_all_ints!(impl_int_from_int: i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+impl_for_all_ints!(impl_int_from_float: f32, f64);
+impl_float_from_int!(i8 => f32, f64);
+impl_float_from_int!(i16 => f32, f64);
+impl_float_from_int!(i32 => f32, f64);
+impl_float_from_int!(i64 => f32, f64);
+impl_float_from_int!(i128 => f32, f64);
+impl_float_from_int!(isize => f32, f64);
+impl_float_from_int!(u8 => f32, f64);
+impl_float_from_int!(u16 => f32, f64);
+impl_float_from_int!(u32 => f32, f64);
+impl_float_from_int!(u64 => f32, f64);
+impl_float_from_int!(u128 => f32, f64);
+impl_float_from_int!(usize => f32, f64);
+impl_float_from_float!(f32 => f32, f64);
+impl_float_from_float!(f64 => f32, f64);
+impl_int_from_char!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl CastFrom<u8> for char {
+    fn checked_cast_from(src: u8) -> Option<Self> {
+        Some(src as char)
+    }
+
+    fn saturating_cast_from(src: u8) -> Self {
+        src as char
+    }
+
+    fn wrapping_cast_from(src: u8) -> Self {
+        src as char
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every value printed by `casting()` in `05_types.rs`, next to the
+    // policy that reproduces it.
+    #[test]
+    fn test_casting_example_table() {
+        let decimal = 65.4321_f32;
+        let integer: u8 = saturating_cast(decimal);
+        let character: char = wrapping_cast(integer);
+        assert_eq!((integer, character), (65, 'A'));
+
+        assert_eq!(checked_cast::<i32, u16>(1000), Some(1000));
+        assert_eq!(wrapping_cast::<i32, u8>(1000), 232);
+        assert_eq!(wrapping_cast::<i8, u8>(-1), 255);
+        assert_eq!(checked_cast::<i32, i16>(128), Some(128));
+        assert_eq!(wrapping_cast::<i32, i8>(128), -128);
+        assert_eq!(wrapping_cast::<i32, i8>(232), -24);
+
+        assert_eq!(saturating_cast::<f32, u8>(300.0), 255);
+        assert_eq!(saturating_cast::<f32, u8>(-100.0), 0);
+        assert_eq!(saturating_cast::<f32, u8>(f32::NAN), 0);
+
+        // What `to_int_unchecked` printed for the same inputs.
+        assert_eq!(wrapping_cast::<f32, u8>(300.0), 44);
+        assert_eq!(wrapping_cast::<f32, u8>(-100.0), 156);
+        assert_eq!(wrapping_cast::<f32, u8>(f32::NAN), 0);
+    }
+
+    #[test]
+    fn test_checked_rejects_what_as_changes() {
+        assert_eq!(checked_cast::<i32, u8>(1000), None);
+        assert_eq!(checked_cast::<i8, u8>(-1), None);
+        assert_eq!(checked_cast::<i32, i8>(128), None);
+        assert_eq!(checked_cast::<f32, u8>(300.0), None);
+        assert_eq!(checked_cast::<f32, u8>(-100.0), None);
+        assert_eq!(checked_cast::<f32, u8>(f32::NAN), None);
+        assert_eq!(checked_cast::<f32, u8>(f32::INFINITY), None);
+        assert_eq!(checked_cast::<f32, u8>(65.4321), Some(65));
+        assert_eq!(checked_cast::<f32, u8>(-0.5), Some(0));
+        assert_eq!(checked_cast::<char, u8>('é'), Some(233));
+        assert_eq!(checked_cast::<char, u8>('€'), None);
+    }
+
+    #[test]
+    fn test_float_bounds_are_exact() {
+        // 2^31 rounds to the same f32 as i32::MAX, but does not fit.
+        assert_eq!(checked_cast::<f32, i32>(2_147_483_648.0), None);
+        assert_eq!(checked_cast::<f32, i32>(-2_147_483_648.0), Some(i32::MIN));
+        assert_eq!(checked_cast::<f64, i32>(2_147_483_647.9), Some(i32::MAX));
+        assert_eq!(checked_cast::<f64, u64>(18_446_744_073_709_551_616.0), None);
+        assert_eq!(checked_cast::<f32, u128>(f32::MAX), Some(f32::MAX as u128));
+    }
+
+    #[test]
+    fn test_int_to_float() {
+        assert_eq!(checked_cast::<i32, f32>(16_777_216), Some(16_777_216.0));
+        assert_eq!(checked_cast::<i32, f32>(16_777_217), None);
+        assert_eq!(checked_cast::<u128, f32>(u128::MAX), None);
+        assert_eq!(saturating_cast::<u128, f32>(u128::MAX), f32::MAX);
+        assert_eq!(wrapping_cast::<u128, f32>(u128::MAX), f32::INFINITY);
+        assert_eq!(
+            checked_cast::<i64, f64>(-(1 << 53)),
+            Some(-9_007_199_254_740_992.0)
+        );
+    }
+
+    #[test]
+    fn test_float_to_float() {
+        assert_eq!(checked_cast::<f32, f64>(0.1), Some(0.1_f32 as f64));
+        assert_eq!(checked_cast::<f64, f32>(0.5), Some(0.5));
+        assert_eq!(checked_cast::<f64, f32>(0.1), None);
+        assert_eq!(checked_cast::<f64, f32>(1e300), None);
+        assert!(checked_cast::<f64, f32>(f64::NAN).unwrap().is_nan());
+        assert_eq!(saturating_cast::<f64, f32>(1e300), f32::MAX);
+        assert_eq!(saturating_cast::<f64, f32>(-1e300), f32::MIN);
+        assert!(saturating_cast::<f64, f32>(f64::NAN).is_nan());
+        assert_eq!(wrapping_cast::<f64, f32>(1e300), f32::INFINITY);
+
+        // Infinities are in range of every float type, for every policy.
+        for infinity in [f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(checked_cast::<f64, f64>(infinity), Some(infinity));
+            assert_eq!(saturating_cast::<f64, f64>(infinity), infinity);
+            assert_eq!(wrapping_cast::<f64, f64>(infinity), infinity);
+            let narrow = infinity as f32;
+            assert_eq!(checked_cast::<f64, f32>(infinity), Some(narrow));
+            assert_eq!(saturating_cast::<f64, f32>(infinity), narrow);
+            assert_eq!(wrapping_cast::<f64, f32>(infinity), narrow);
+            assert_eq!(checked_cast::<f32, f64>(narrow), Some(infinity));
+            assert_eq!(saturating_cast::<f32, f64>(narrow), infinity);
+            assert_eq!(wrapping_cast::<f32, f64>(narrow), infinity);
+            assert_eq!(saturating_cast::<f32, f32>(narrow), narrow);
+        }
+    }
+
+    #[test]
+    fn test_saturating_int_to_int() {
+        assert_eq!(saturating_cast::<i32, i8>(1000), i8::MAX);
+        assert_eq!(saturating_cast::<i32, i8>(-1000), i8::MIN);
+        assert_eq!(saturating_cast::<u64, i64>(u64::MAX), i64::MAX);
+        assert_eq!(saturating_cast::<i128, u128>(i128::MIN), 0);
+        assert_eq!(saturating_cast::<char, u8>('€'), u8::MAX);
+    }
+
+    #[test]
+    fn test_wrapping_float_matches_int_wrapping() {
+        for value in [
+            -1e6_f64, -300.0, -129.7, -1.0, -0.0, 0.0, 1.5, 255.0, 256.0, 70_000.0,
+        ] {
+            let truncated = value.trunc() as i64;
+            assert_eq!(wrapping_cast::<f64, u8>(value), truncated as u8, "{value}");
+            assert_eq!(wrapping_cast::<f64, i8>(value), truncated as i8, "{value}");
+            assert_eq!(
+                wrapping_cast::<f64, u16>(value),
+                truncated as u16,
+                "{value}"
+            );
+        }
+        assert_eq!(wrapping_cast::<f64, u8>(f64::INFINITY), 0);
+        assert_eq!(wrapping_cast::<f64, u128>(-3.0), u128::MAX - 2);
+        assert_eq!(wrapping_cast::<f64, u32>(1e30), 0);
+    }
+
+    // Any value that survives a checked cast must be what `as` produces, and
+    // must agree with the other two policies.
+    #[test]
+    fn test_policies_agree_when_in_range() {
+        for value in i16::MIN..=i16::MAX {
+            if let Some(narrow) = checked_cast::<i16, u8>(value) {
+                assert_eq!(narrow, value as u8);
+                assert_eq!(saturating_cast::<i16, u8>(value), narrow);
+                assert_eq!(wrapping_cast::<i16, u8>(value), narrow);
+            } else {
+                assert!(!(0..=255).contains(&value));
+            }
+        }
+    }
+}
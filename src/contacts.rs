@@ -0,0 +1,560 @@
+//! A contact directory built on `Person` from `24_meta_documentation.rs`.
+//!
+//! Unlike the doctests in a binary, the ones in this module really run under
+//! `cargo test --doc`, because they can name the library crate.
+//!
+//! ```
+//! use rust_by_example::contacts::{Directory, Person};
+//!
+//! let mut directory = Directory::new();
+//! directory.add(Person::new("Juliet Capulet")?.with_email("juliet@verona.it")?)?;
+//! directory.add(Person::new("Romeo Montague")?)?;
+//!
+//! let found: Vec<_> = directory.search("jul").map(|p| p.name()).collect();
+//! assert_eq!(found, ["Juliet Capulet"]);
+//! # Ok::<(), rust_by_example::contacts::ContactError>(())
+//! ```
+
+use crate::i18n::Catalog;
+use std::{cmp::Ordering, error, fmt, str::FromStr};
+
+/// Why a contact or a directory change was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContactError {
+    EmptyName,
+    /// Names may not contain the `|` separator or line breaks.
+    InvalidName(String),
+    InvalidEmail(String),
+    InvalidBirthday(String),
+    DuplicateName(String),
+    /// A line of the text format with more than three fields.
+    InvalidLine(String),
+    /// An imported line could not be read; `line` counts from 1.
+    Import {
+        line: usize,
+        error: Box<ContactError>,
+    },
+}
+
+impl fmt::Display for ContactError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContactError::EmptyName => write!(f, "a person must have a name"),
+            ContactError::InvalidName(name) => write!(f, "invalid name `{}`", name),
+            ContactError::InvalidEmail(email) => write!(f, "invalid email address `{}`", email),
+            ContactError::InvalidBirthday(date) => {
+                write!(f, "invalid birthday `{}`, expected YYYY-MM-DD", date)
+            }
+            ContactError::DuplicateName(name) => write!(f, "{} is already in the directory", name),
+            ContactError::InvalidLine(line) => {
+                write!(f, "expected `name|email|birthday`, got `{}`", line)
+            }
+            ContactError::Import { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl error::Error for ContactError {}
+
+/// A calendar date, validated on construction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Birthday {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl Birthday {
+    /// Returns `None` unless the date exists, including leap days.
+    pub fn new(year: u16, month: u8, day: u8) -> Option<Birthday> {
+        let leap =
+            year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+        let days_in_month = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return None,
+        };
+        (1..=days_in_month)
+            .contains(&day)
+            .then_some(Birthday { year, month, day })
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+}
+
+impl FromStr for Birthday {
+    type Err = ContactError;
+
+    /// Parses an ISO 8601 date such as `1996-02-29`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ContactError::InvalidBirthday(s.to_string());
+        let mut parts = s.split('-');
+        let (Some(year), Some(month), Some(day), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+            return Err(invalid());
+        }
+        // `parse` would also accept a leading `+`.
+        if ![year, month, day]
+            .iter()
+            .all(|field| field.bytes().all(|b| b.is_ascii_digit()))
+        {
+            return Err(invalid());
+        }
+        let (Ok(year), Ok(month), Ok(day)) = (year.parse(), month.parse(), day.parse()) else {
+            return Err(invalid());
+        };
+        Birthday::new(year, month, day).ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for Birthday {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// A human being is represented here
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Person {
+    /// A person must have a name, no matter how much Juliet may hate it
+    name: String,
+    email: Option<String>,
+    birthday: Option<Birthday>,
+}
+
+impl Person {
+    /// Creates a person with the given name.
+    ///
+    /// Surrounding whitespace is trimmed. The name must not be empty and
+    /// must not contain `|` or line breaks, which the text format reserves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // You can have rust code between fences inside the comments
+    /// // If you pass --test to `rustdoc`, it will even test it for you!
+    /// use rust_by_example::contacts::Person;
+    /// let person = Person::new("name").unwrap();
+    /// assert_eq!(person.name(), "name");
+    /// assert!(Person::new("  ").is_err());
+    /// ```
+    pub fn new(name: &str) -> Result<Person, ContactError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(ContactError::EmptyName);
+        }
+        if name.contains(['|', '\n', '\r']) {
+            return Err(ContactError::InvalidName(name.to_string()));
+        }
+        Ok(Person {
+            name: name.to_string(),
+            email: None,
+            birthday: None,
+        })
+    }
+
+    /// Adds an email address, which needs a non-empty local part and a
+    /// dotted domain, and no whitespace.
+    ///
+    /// ```
+    /// use rust_by_example::contacts::Person;
+    /// let romeo = Person::new("Romeo").unwrap();
+    /// assert!(romeo.clone().with_email("romeo@montague.it").is_ok());
+    /// assert!(romeo.with_email("romeo at montague").is_err());
+    /// ```
+    pub fn with_email(mut self, email: &str) -> Result<Person, ContactError> {
+        let email = email.trim();
+        let valid = match email.split_once('@') {
+            Some((local, domain)) => {
+                !local.is_empty()
+                    && !domain.contains('@')
+                    && domain.split('.').count() >= 2
+                    && domain.split('.').all(|label| !label.is_empty())
+                    && !email.contains(|c: char| c.is_whitespace() || c == '|')
+            }
+            None => false,
+        };
+        if !valid {
+            return Err(ContactError::InvalidEmail(email.to_string()));
+        }
+        self.email = Some(email.to_string());
+        Ok(self)
+    }
+
+    /// Adds a birthday in `YYYY-MM-DD` form.
+    pub fn with_birthday(mut self, birthday: &str) -> Result<Person, ContactError> {
+        self.birthday = Some(birthday.trim().parse()?);
+        Ok(self)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    pub fn birthday(&self) -> Option<Birthday> {
+        self.birthday
+    }
+
+    /// The friendly hello from [`hello`](Person::hello), in the language
+    /// of `catalog`.
+    ///
+    /// ```
+    /// use rust_by_example::{contacts::Person, i18n::Catalog};
+    ///
+    /// let juliet = Person::new("Juliet").unwrap();
+    /// assert_eq!(juliet.greeting(Catalog::english()), "Hello, Juliet!");
+    /// ```
+    pub fn greeting(&self, catalog: &Catalog) -> String {
+        catalog.get("hello.person", &[("name", &self.name)])
+    }
+
+    /// Gives a friendly hello!
+    ///
+    /// Says "Hello, [name](Person::name)" to the `Person` it is called on,
+    /// in the language picked by `LC_ALL` or `LANG`.
+    pub fn hello(&self) {
+        println!("{}", self.greeting(&Catalog::from_env()));
+    }
+
+    /// One line of the directory text format: `name|email|birthday`, with
+    /// missing fields left empty.
+    pub fn to_line(&self) -> String {
+        format!(
+            "{}|{}|{}",
+            self.name,
+            self.email.as_deref().unwrap_or(""),
+            self.birthday.map(|b| b.to_string()).unwrap_or_default()
+        )
+    }
+
+    /// Parses a line written by [`to_line`](Person::to_line). Trailing
+    /// empty fields may be left out.
+    pub fn from_line(line: &str) -> Result<Person, ContactError> {
+        if line.split('|').count() > 3 {
+            return Err(ContactError::InvalidLine(line.to_string()));
+        }
+        let mut fields = line.split('|');
+        let mut person = Person::new(fields.next().unwrap_or(""))?;
+        if let Some(email) = fields.next().filter(|f| !f.trim().is_empty()) {
+            person = person.with_email(email)?;
+        }
+        if let Some(birthday) = fields.next().filter(|f| !f.trim().is_empty()) {
+            person = person.with_birthday(birthday)?;
+        }
+        Ok(person)
+    }
+}
+
+/// The orders a [`Directory`] can be sorted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Case-insensitive, by name.
+    Name,
+    /// Oldest first; people without a birthday go last.
+    Birthday,
+    /// Case-insensitive, by address; people without one go last.
+    Email,
+}
+
+/// A list of people with unique names.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Directory {
+    people: Vec<Person>,
+}
+
+impl Directory {
+    pub fn new() -> Directory {
+        Directory::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.people.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.people.is_empty()
+    }
+
+    pub fn people(&self) -> &[Person] {
+        &self.people
+    }
+
+    /// Adds `person`, unless someone with the same name (ignoring case) is
+    /// already listed.
+    pub fn add(&mut self, person: Person) -> Result<(), ContactError> {
+        if self.get(&person.name).is_some() {
+            return Err(ContactError::DuplicateName(person.name));
+        }
+        self.people.push(person);
+        Ok(())
+    }
+
+    /// Looks a person up by name, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&Person> {
+        let name = name.to_lowercase();
+        self.people.iter().find(|p| p.name.to_lowercase() == name)
+    }
+
+    /// Removes a person by name, ignoring case, and returns them.
+    pub fn remove(&mut self, name: &str) -> Option<Person> {
+        let name = name.to_lowercase();
+        let index = self
+            .people
+            .iter()
+            .position(|p| p.name.to_lowercase() == name)?;
+        Some(self.people.remove(index))
+    }
+
+    /// Every person whose name, or any word of it, starts with `prefix`,
+    /// ignoring case.
+    ///
+    /// ```
+    /// use rust_by_example::contacts::{Directory, Person};
+    ///
+    /// let mut directory = Directory::new();
+    /// for name in ["Juliet Capulet", "Tybalt Capulet", "Romeo Montague"] {
+    ///     directory.add(Person::new(name)?)?;
+    /// }
+    /// assert_eq!(directory.search("CAP").count(), 2);
+    /// assert_eq!(directory.search("r").count(), 1);
+    /// # Ok::<(), rust_by_example::contacts::ContactError>(())
+    /// ```
+    pub fn search<'a>(&'a self, prefix: &str) -> impl Iterator<Item = &'a Person> + 'a {
+        let prefix = prefix.to_lowercase();
+        self.people.iter().filter(move |person| {
+            let name = person.name.to_lowercase();
+            name.starts_with(&prefix)
+                || name
+                    .split_whitespace()
+                    .any(|word| word.starts_with(&prefix))
+        })
+    }
+
+    /// Sorts the directory in place. The sort is stable, and ties on
+    /// birthday or email fall back to the name.
+    pub fn sort_by(&mut self, key: SortKey) {
+        fn missing_last<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+            match (a, b) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        }
+
+        let by_name = |a: &Person, b: &Person| a.name.to_lowercase().cmp(&b.name.to_lowercase());
+        match key {
+            SortKey::Name => self.people.sort_by(by_name),
+            SortKey::Birthday => self
+                .people
+                .sort_by(|a, b| missing_last(a.birthday, b.birthday).then_with(|| by_name(a, b))),
+            SortKey::Email => self.people.sort_by(|a, b| {
+                let email = |p: &Person| p.email.as_ref().map(|e| e.to_lowercase());
+                missing_last(email(a), email(b)).then_with(|| by_name(a, b))
+            }),
+        }
+    }
+
+    /// Writes one [`Person::to_line`] per person.
+    pub fn export(&self) -> String {
+        self.people
+            .iter()
+            .map(|person| person.to_line() + "\n")
+            .collect()
+    }
+
+    /// Reads the format written by [`export`](Directory::export). Blank
+    /// lines and lines starting with `#` are ignored.
+    ///
+    /// ```
+    /// use rust_by_example::contacts::Directory;
+    ///
+    /// let text = "# name|email|birthday\nJuliet|juliet@verona.it|1583-07-31\nRomeo\n";
+    /// let directory = Directory::import(text)?;
+    /// assert_eq!(directory.len(), 2);
+    /// assert_eq!(directory.export(), "Juliet|juliet@verona.it|1583-07-31\nRomeo||\n");
+    /// # Ok::<(), rust_by_example::contacts::ContactError>(())
+    /// ```
+    pub fn import(text: &str) -> Result<Directory, ContactError> {
+        let mut directory = Directory::new();
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            Person::from_line(line)
+                .and_then(|person| directory.add(person))
+                .map_err(|error| ContactError::Import {
+                    line: index + 1,
+                    error: Box::new(error),
+                })?;
+        }
+        Ok(directory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn person(name: &str, email: Option<&str>, birthday: Option<&str>) -> Person {
+        let mut person = Person::new(name).unwrap();
+        if let Some(email) = email {
+            person = person.with_email(email).unwrap();
+        }
+        if let Some(birthday) = birthday {
+            person = person.with_birthday(birthday).unwrap();
+        }
+        person
+    }
+
+    fn names(directory: &Directory) -> Vec<&str> {
+        directory.people().iter().map(Person::name).collect()
+    }
+
+    #[test]
+    fn test_validation() {
+        assert_eq!(Person::new(""), Err(ContactError::EmptyName));
+        assert!(Person::new("a|b").is_err());
+        let juliet = Person::new(" Juliet ").unwrap();
+        assert_eq!(juliet.greeting(Catalog::english()), "Hello, Juliet!");
+        for email in [
+            "",
+            "juliet",
+            "@verona.it",
+            "juliet@verona",
+            "juliet@.it",
+            "a b@c.d",
+        ] {
+            assert!(juliet.clone().with_email(email).is_err(), "{email}");
+        }
+        for date in [
+            "2023-02-29",
+            "2023-13-01",
+            "2023-1-01",
+            "20230101",
+            "2023-+1-01",
+            "+123-01-01",
+            "2023-01-+1",
+            "2023-04-31",
+        ] {
+            assert!(juliet.clone().with_birthday(date).is_err(), "{date}");
+        }
+        assert!(juliet.clone().with_birthday("2000-02-29").is_ok());
+        assert!(juliet.with_birthday("1900-02-29").is_err());
+    }
+
+    #[test]
+    fn test_duplicates_ignore_case() {
+        let mut directory = Directory::new();
+        directory.add(person("Juliet", None, None)).unwrap();
+        assert_eq!(
+            directory.add(person("JULIET", None, None)),
+            Err(ContactError::DuplicateName("JULIET".to_string()))
+        );
+        assert_eq!(
+            directory.remove("juliet").map(|p| p.name),
+            Some("Juliet".into())
+        );
+        assert!(directory.is_empty());
+    }
+
+    #[test]
+    fn test_search() {
+        let mut directory = Directory::new();
+        for name in ["Juliet Capulet", "Tybalt Capulet", "Romeo Montague"] {
+            directory.add(person(name, None, None)).unwrap();
+        }
+        let found = |prefix| -> Vec<&str> { directory.search(prefix).map(Person::name).collect() };
+        assert_eq!(found("cap"), ["Juliet Capulet", "Tybalt Capulet"]);
+        assert_eq!(found("juliet c"), ["Juliet Capulet"]);
+        assert_eq!(found("Juliet Capulet"), ["Juliet Capulet"]);
+        assert!(found("juliet m").is_empty());
+        assert!(found("capulet t").is_empty());
+    }
+
+    #[test]
+    fn test_sorting() {
+        let mut directory = Directory::new();
+        directory
+            .add(person("romeo", Some("r@m.it"), None))
+            .unwrap();
+        directory
+            .add(person("Mercutio", None, Some("1580-01-01")))
+            .unwrap();
+        directory
+            .add(person("Juliet", Some("J@v.it"), Some("1583-07-31")))
+            .unwrap();
+        directory.add(person("Benvolio", None, None)).unwrap();
+
+        directory.sort_by(SortKey::Name);
+        assert_eq!(
+            names(&directory),
+            ["Benvolio", "Juliet", "Mercutio", "romeo"]
+        );
+        directory.sort_by(SortKey::Birthday);
+        assert_eq!(
+            names(&directory),
+            ["Mercutio", "Juliet", "Benvolio", "romeo"]
+        );
+        directory.sort_by(SortKey::Email);
+        assert_eq!(
+            names(&directory),
+            ["Juliet", "romeo", "Benvolio", "Mercutio"]
+        );
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let mut directory = Directory::new();
+        directory
+            .add(person(
+                "Juliet Capulet",
+                Some("juliet@verona.it"),
+                Some("1583-07-31"),
+            ))
+            .unwrap();
+        directory
+            .add(person("Friar Laurence", None, Some("1540-03-01")))
+            .unwrap();
+        directory
+            .add(person("Romeo", Some("romeo@montague.it"), None))
+            .unwrap();
+        let text = directory.export();
+        assert_eq!(Directory::import(&text), Ok(directory));
+    }
+
+    #[test]
+    fn test_import_errors_name_the_line() {
+        let error = Directory::import("Juliet\n\nRomeo|not-an-email\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 3: invalid email address `not-an-email`"
+        );
+        let error = Directory::import("Juliet\njuliet\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: juliet is already in the directory"
+        );
+        assert!(Directory::import("Juliet|||extra").is_err());
+    }
+}
@@ -0,0 +1,565 @@
+//! An event bus built on the three closure traits.
+//!
+//! `09_functions_closures_5.rs` returns an `impl Fn`, an `impl FnMut` and an
+//! `impl FnOnce`. An [`EventBus`] accepts all three as handlers: `Fn`
+//! handlers just observe, `FnMut` handlers keep state between events, and
+//! `FnOnce` handlers are removed after the first event they see.
+//!
+//! Subscribing returns a [`Subscription`]; dropping it unsubscribes the
+//! handler. [`SyncEventBus`] is the same bus for handlers that are `Send`,
+//! so that it can be shared between threads.
+//!
+//! ```
+//! use std::{cell::Cell, rc::Rc};
+//! use rust_by_example::events::EventBus;
+//!
+//! let bus = EventBus::new();
+//! let total = Rc::new(Cell::new(0));
+//!
+//! let sum = Rc::clone(&total);
+//! let subscription = bus.subscribe(move |n: &i32| sum.set(sum.get() + n));
+//! let _greeting = bus.subscribe_once(|n: &i32| println!("first event: {}", n));
+//!
+//! assert_eq!(bus.publish(&3), 2);
+//! assert_eq!(bus.publish(&4), 1);
+//! drop(subscription);
+//! assert_eq!(bus.publish(&5), 0);
+//! assert_eq!(total.get(), 7);
+//! ```
+
+use std::{
+    sync::{self, Arc, Mutex, MutexGuard, PoisonError},
+    thread::{self, ThreadId},
+};
+
+/// The closures a bus accepts: any for an [`EventBus`], `Send` ones for a
+/// [`SyncEventBus`].
+trait Kind<E> {
+    type Fn: ?Sized + Fn(&E);
+    type FnMut: ?Sized + FnMut(&E);
+    type FnOnce: ?Sized + FnOnce(&E);
+}
+
+enum Local {}
+
+impl<E> Kind<E> for Local {
+    type Fn = dyn Fn(&E);
+    type FnMut = dyn FnMut(&E);
+    type FnOnce = dyn FnOnce(&E);
+}
+
+enum Threaded {}
+
+impl<E> Kind<E> for Threaded {
+    type Fn = dyn Fn(&E) + Send;
+    type FnMut = dyn FnMut(&E) + Send;
+    type FnOnce = dyn FnOnce(&E) + Send;
+}
+
+enum Handler<E, K: Kind<E>> {
+    Fn(Box<K::Fn>),
+    FnMut(Box<K::FnMut>),
+    FnOnce(Option<Box<K::FnOnce>>),
+}
+
+impl<E, K: Kind<E>> Handler<E, K> {
+    /// Calls the handler, returning whether it can be called again.
+    fn call(&mut self, event: &E) -> bool {
+        match self {
+            Handler::Fn(f) => f(event),
+            Handler::FnMut(f) => f(event),
+            Handler::FnOnce(f) => {
+                if let Some(f) = f.take() {
+                    f(event);
+                }
+                return false;
+            }
+        }
+        true
+    }
+}
+
+struct Registry<H> {
+    next_id: u64,
+    handlers: Vec<(u64, H)>,
+}
+
+impl<H: Clone> Registry<H> {
+    fn new() -> Registry<H> {
+        Registry {
+            next_id: 0,
+            handlers: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, handler: H) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.handlers.push((id, handler));
+        id
+    }
+
+    fn remove(&mut self, id: u64) {
+        self.handlers.retain(|(other, _)| *other != id);
+    }
+
+    fn get(&self, id: u64) -> Option<H> {
+        self.handlers
+            .iter()
+            .find(|(other, _)| *other == id)
+            .map(|(_, handler)| handler.clone())
+    }
+
+    fn ids(&self) -> Vec<u64> {
+        self.handlers.iter().map(|(id, _)| *id).collect()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // A panicking handler must not take the whole bus down with it.
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A subscribed handler, and the thread that is calling it, if any.
+struct Slot<E, K: Kind<E>> {
+    handler: Mutex<Handler<E, K>>,
+    running_on: Mutex<Option<ThreadId>>,
+}
+
+// Marks a handler as running on the current thread until it is dropped,
+// even if the handler panics.
+struct Running<'a>(&'a Mutex<Option<ThreadId>>);
+
+impl<'a> Running<'a> {
+    fn start(running_on: &'a Mutex<Option<ThreadId>>) -> Running<'a> {
+        *lock(running_on) = Some(thread::current().id());
+        Running(running_on)
+    }
+}
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        *lock(self.0) = None;
+    }
+}
+
+type Hub<E, K> = Mutex<Registry<Arc<Slot<E, K>>>>;
+
+// What `EventBus` and `SyncEventBus` share; they differ only in the
+// handlers they accept.
+struct Bus<E, K: Kind<E>> {
+    registry: Arc<Hub<E, K>>,
+}
+
+impl<E, K: Kind<E>> Bus<E, K> {
+    fn new() -> Bus<E, K> {
+        Bus {
+            registry: Arc::new(Mutex::new(Registry::new())),
+        }
+    }
+
+    fn add(&self, handler: Handler<E, K>) -> Handle<E, K> {
+        let id = lock(&self.registry).add(Arc::new(Slot {
+            handler: Mutex::new(handler),
+            running_on: Mutex::new(None),
+        }));
+        Handle {
+            registry: Arc::downgrade(&self.registry),
+            id,
+        }
+    }
+
+    fn publish(&self, event: &E) -> usize {
+        let mut called = 0;
+        // Look every handler up again, since earlier handlers may have
+        // unsubscribed it.
+        let ids = lock(&self.registry).ids();
+        for id in ids {
+            let Some(slot) = lock(&self.registry).get(id) else {
+                continue;
+            };
+            // A handler that publishes does not see its own events. Only
+            // other threads wait for it to finish.
+            if *lock(&slot.running_on) == Some(thread::current().id()) {
+                continue;
+            }
+            let mut handler = lock(&slot.handler);
+            // Another thread may have fired a one-shot handler meanwhile.
+            if matches!(*handler, Handler::FnOnce(None)) {
+                continue;
+            }
+            let _running = Running::start(&slot.running_on);
+            called += 1;
+            if !handler.call(event) {
+                lock(&self.registry).remove(id);
+            }
+        }
+        called
+    }
+
+    fn len(&self) -> usize {
+        lock(&self.registry).handlers.len()
+    }
+}
+
+impl<E, K: Kind<E>> Clone for Bus<E, K> {
+    fn clone(&self) -> Self {
+        Bus {
+            registry: Arc::clone(&self.registry),
+        }
+    }
+}
+
+struct Handle<E, K: Kind<E>> {
+    registry: sync::Weak<Hub<E, K>>,
+    id: u64,
+}
+
+impl<E, K: Kind<E>> Handle<E, K> {
+    fn is_active(&self) -> bool {
+        self.registry
+            .upgrade()
+            .is_some_and(|registry| lock(&registry).get(self.id).is_some())
+    }
+
+    fn detach(&mut self) {
+        self.registry = sync::Weak::new();
+    }
+}
+
+impl<E, K: Kind<E>> Drop for Handle<E, K> {
+    fn drop(&mut self) {
+        if let Some(registry) = self.registry.upgrade() {
+            lock(&registry).remove(self.id);
+        }
+    }
+}
+
+/// A single-threaded publish/subscribe hub for events of type `E`.
+///
+/// Cloning the bus gives another handle to the same subscribers, so a
+/// handler can capture a clone and publish further events. A handler that
+/// is already running is skipped by such nested publishes.
+pub struct EventBus<E> {
+    bus: Bus<E, Local>,
+}
+
+impl<E> EventBus<E> {
+    pub fn new() -> EventBus<E> {
+        EventBus { bus: Bus::new() }
+    }
+
+    /// Subscribes a handler that is called for every event.
+    pub fn subscribe<F: Fn(&E) + 'static>(&self, handler: F) -> Subscription<E> {
+        Subscription {
+            handle: self
+                .bus
+                .add(Handler::Fn(Box::new(handler) as Box<dyn Fn(&E)>)),
+        }
+    }
+
+    /// Subscribes a handler that can change its own state.
+    pub fn subscribe_mut<F: FnMut(&E) + 'static>(&self, handler: F) -> Subscription<E> {
+        Subscription {
+            handle: self
+                .bus
+                .add(Handler::FnMut(Box::new(handler) as Box<dyn FnMut(&E)>)),
+        }
+    }
+
+    /// Subscribes a handler that is called for the next event only.
+    pub fn subscribe_once<F: FnOnce(&E) + 'static>(&self, handler: F) -> Subscription<E> {
+        Subscription {
+            handle: self.bus.add(Handler::FnOnce(Some(
+                Box::new(handler) as Box<dyn FnOnce(&E)>
+            ))),
+        }
+    }
+
+    /// Calls every subscribed handler with `event`, in the order they
+    /// subscribed, and returns how many were called.
+    pub fn publish(&self, event: &E) -> usize {
+        self.bus.publish(event)
+    }
+
+    /// The number of subscribed handlers.
+    pub fn len(&self) -> usize {
+        self.bus.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<E> Default for EventBus<E> {
+    fn default() -> Self {
+        EventBus::new()
+    }
+}
+
+impl<E> Clone for EventBus<E> {
+    fn clone(&self) -> Self {
+        EventBus {
+            bus: self.bus.clone(),
+        }
+    }
+}
+
+/// Keeps a handler subscribed to an [`EventBus`] until it is dropped.
+#[must_use = "dropping a subscription unsubscribes its handler right away"]
+pub struct Subscription<E> {
+    handle: Handle<E, Local>,
+}
+
+impl<E> Subscription<E> {
+    /// Whether the handler will still be called. One-shot handlers become
+    /// inactive once they have fired.
+    pub fn is_active(&self) -> bool {
+        self.handle.is_active()
+    }
+
+    /// Drops the handle but keeps the handler subscribed for as long as the
+    /// bus lives.
+    pub fn detach(mut self) {
+        self.handle.detach();
+    }
+}
+
+/// An [`EventBus`] that can be shared between threads.
+///
+/// Events may be published from several threads at once; each handler
+/// still sees one event at a time. As on an [`EventBus`], a handler that
+/// is already running is skipped by the events it publishes itself.
+pub struct SyncEventBus<E> {
+    bus: Bus<E, Threaded>,
+}
+
+impl<E> SyncEventBus<E> {
+    pub fn new() -> SyncEventBus<E> {
+        SyncEventBus { bus: Bus::new() }
+    }
+
+    /// Subscribes a handler that is called for every event.
+    pub fn subscribe<F: Fn(&E) + Send + 'static>(&self, handler: F) -> SyncSubscription<E> {
+        SyncSubscription {
+            handle: self
+                .bus
+                .add(Handler::Fn(Box::new(handler) as Box<dyn Fn(&E) + Send>)),
+        }
+    }
+
+    /// Subscribes a handler that can change its own state.
+    pub fn subscribe_mut<F: FnMut(&E) + Send + 'static>(&self, handler: F) -> SyncSubscription<E> {
+        SyncSubscription {
+            handle: self.bus.add(Handler::FnMut(
+                Box::new(handler) as Box<dyn FnMut(&E) + Send>
+            )),
+        }
+    }
+
+    /// Subscribes a handler that is called for the next event only. If
+    /// several threads publish at once, exactly one of them calls it.
+    pub fn subscribe_once<F: FnOnce(&E) + Send + 'static>(
+        &self,
+        handler: F,
+    ) -> SyncSubscription<E> {
+        SyncSubscription {
+            handle: self.bus.add(Handler::FnOnce(Some(
+                Box::new(handler) as Box<dyn FnOnce(&E) + Send>
+            ))),
+        }
+    }
+
+    /// Calls every subscribed handler with `event`, in the order they
+    /// subscribed, and returns how many were called.
+    pub fn publish(&self, event: &E) -> usize {
+        self.bus.publish(event)
+    }
+
+    /// The number of subscribed handlers.
+    pub fn len(&self) -> usize {
+        self.bus.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<E> Default for SyncEventBus<E> {
+    fn default() -> Self {
+        SyncEventBus::new()
+    }
+}
+
+impl<E> Clone for SyncEventBus<E> {
+    fn clone(&self) -> Self {
+        SyncEventBus {
+            bus: self.bus.clone(),
+        }
+    }
+}
+
+/// Keeps a handler subscribed to a [`SyncEventBus`] until it is dropped.
+#[must_use = "dropping a subscription unsubscribes its handler right away"]
+pub struct SyncSubscription<E> {
+    handle: Handle<E, Threaded>,
+}
+
+impl<E> SyncSubscription<E> {
+    /// Whether the handler will still be called. One-shot handlers become
+    /// inactive once they have fired.
+    pub fn is_active(&self) -> bool {
+        self.handle.is_active()
+    }
+
+    /// Drops the handle but keeps the handler subscribed for as long as the
+    /// bus lives.
+    pub fn detach(mut self) {
+        self.handle.detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    #[test]
+    fn test_handler_kinds() {
+        let bus = EventBus::new();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+
+        let log = Rc::clone(&seen);
+        let _all = bus.subscribe(move |e: &&str| log.borrow_mut().push(format!("fn {}", e)));
+        let mut count = 0;
+        let log = Rc::clone(&seen);
+        let _counter = bus.subscribe_mut(move |_: &&str| {
+            count += 1;
+            log.borrow_mut().push(format!("count {}", count));
+        });
+        let log = Rc::clone(&seen);
+        let once = bus.subscribe_once(move |e: &&str| log.borrow_mut().push(format!("once {}", e)));
+
+        assert!(once.is_active());
+        assert_eq!(bus.publish(&"a"), 3);
+        assert!(!once.is_active());
+        assert_eq!(bus.len(), 2);
+        assert_eq!(bus.publish(&"b"), 2);
+        assert_eq!(
+            *seen.borrow(),
+            ["fn a", "count 1", "once a", "fn b", "count 2"]
+        );
+    }
+
+    #[test]
+    fn test_drop_and_detach() {
+        let bus = EventBus::new();
+        let calls = Rc::new(Cell::new(0));
+
+        let counter = Rc::clone(&calls);
+        let dropped = bus.subscribe(move |_: &()| counter.set(counter.get() + 1));
+        let counter = Rc::clone(&calls);
+        bus.subscribe(move |_: &()| counter.set(counter.get() + 10))
+            .detach();
+        assert_eq!(bus.publish(&()), 2);
+        drop(dropped);
+        assert_eq!(bus.publish(&()), 1);
+        assert_eq!(calls.get(), 21);
+
+        // A subscription may outlive its bus.
+        let orphan = bus.subscribe(|_: &()| {});
+        drop(bus);
+        assert!(!orphan.is_active());
+    }
+
+    #[test]
+    fn test_handlers_can_use_the_bus() {
+        let bus = EventBus::new();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+
+        // Counts down by publishing the next number itself.
+        let inner = bus.clone();
+        let _countdown = bus.subscribe(move |n: &u32| {
+            if *n > 0 {
+                inner.publish(&(n - 1));
+            }
+        });
+        let log = Rc::clone(&seen);
+        let _log = bus.subscribe(move |n: &u32| log.borrow_mut().push(*n));
+
+        // The countdown handler is busy during the nested publishes, so only
+        // the log sees them.
+        bus.publish(&3);
+        assert_eq!(*seen.borrow(), [2, 3]);
+
+        // A one-shot handler that unsubscribes a later one.
+        let slot = Rc::new(RefCell::new(None));
+        let target = Rc::clone(&slot);
+        let _killer = bus.subscribe_once(move |_: &u32| drop(target.borrow_mut().take()));
+        *slot.borrow_mut() = Some(bus.subscribe(|_: &u32| panic!("called after unsubscribing")));
+        assert_eq!(bus.len(), 4);
+        bus.publish(&0);
+        assert!(slot.borrow().is_none());
+        assert_eq!(bus.len(), 2);
+    }
+
+    #[test]
+    fn test_sync_bus_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SyncEventBus<String>>();
+        assert_send_sync::<SyncSubscription<String>>();
+
+        let bus = SyncEventBus::new();
+        let total = Arc::new(AtomicUsize::new(0));
+        let once = Arc::new(AtomicUsize::new(0));
+
+        let sum = Arc::clone(&total);
+        let _sum = bus.subscribe(move |n: &usize| {
+            sum.fetch_add(*n, Ordering::SeqCst);
+        });
+        let fired = Arc::clone(&once);
+        let _once = bus.subscribe_once(move |_: &usize| {
+            fired.fetch_add(1, Ordering::SeqCst);
+        });
+
+        thread::scope(|scope| {
+            for t in 0..4 {
+                let bus = bus.clone();
+                scope.spawn(move || {
+                    for n in 0..100 {
+                        bus.publish(&(t * 100 + n));
+                    }
+                });
+            }
+        });
+
+        assert_eq!(total.load(Ordering::SeqCst), (0..400).sum::<usize>());
+        assert_eq!(once.load(Ordering::SeqCst), 1);
+        assert_eq!(bus.len(), 1);
+    }
+
+    #[test]
+    fn test_sync_handlers_can_use_the_bus() {
+        let bus = SyncEventBus::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        // The same countdown as on an `EventBus`, which must not deadlock.
+        let inner = bus.clone();
+        let _countdown = bus.subscribe(move |n: &u32| {
+            if *n > 0 {
+                inner.publish(&(n - 1));
+            }
+        });
+        let log = Arc::clone(&seen);
+        let _log = bus.subscribe(move |n: &u32| lock(&log).push(*n));
+
+        bus.publish(&3);
+        assert_eq!(*lock(&seen), [2, 3]);
+    }
+}
@@ -0,0 +1,530 @@
+//! An index of the example binaries under `src/bin/`.
+//!
+//! Every example is named after the chapter of *Rust by Example* it
+//! follows, like `14_generics_8_associated_items`. [`ExampleIndex::discover`]
+//! finds them, groups them by chapter and numbers them, and
+//! [`Example::run`] runs one, capturing what it prints and how it exits.
+//!
+//! ```
+//! use rust_by_example::examples::ExampleIndex;
+//!
+//! let index = ExampleIndex::discover(ExampleIndex::DEFAULT_DIR)?;
+//! let example = index.find("14_generics_8")?;
+//! assert_eq!(example.name, "14_generics_8_associated_items");
+//! assert_eq!(example.chapter, Some(14));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    env, error, fmt, fs,
+    io::{self, Read},
+    path::Path,
+    process::{Command, ExitStatus, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Why an example is left out of a full run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tag {
+    /// Waits for input on stdin.
+    Interactive,
+    /// Takes more than a second or two.
+    Slow,
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tag::Interactive => write!(f, "interactive"),
+            Tag::Slow => write!(f, "slow"),
+        }
+    }
+}
+
+/// Examples that a full run skips, and why.
+pub const TAGGED: [(&str, Tag); 2] = [
+    // Reads expressions until the end of input.
+    ("calc", Tag::Interactive),
+    // Waits for `sleep 5`.
+    ("20_std_misc_5_child_processes", Tag::Slow),
+];
+
+/// The chapter titles of *Rust by Example*, by number.
+pub fn chapter_title(chapter: u32) -> &'static str {
+    match chapter {
+        1 => "Hello World",
+        2 => "Primitives",
+        3 => "Custom Types",
+        4 => "Variable Bindings",
+        5 => "Types",
+        6 => "Conversion",
+        7 => "Expressions",
+        8 => "Flow of Control",
+        9 => "Functions",
+        10 => "Modules",
+        11 => "Crates",
+        12 => "Cargo",
+        13 => "Attributes",
+        14 => "Generics",
+        15 => "Scoping Rules",
+        16 => "Traits",
+        17 => "macro_rules!",
+        18 => "Error Handling",
+        19 => "Std Library Types",
+        20 => "Std Misc",
+        21 => "Testing",
+        22 => "Unsafe Operations",
+        23 => "Compatibility",
+        24 => "Meta",
+        _ => "Unknown",
+    }
+}
+
+/// The errors that can occur while finding or running an example.
+#[derive(Debug)]
+pub enum ExampleError {
+    Io(io::Error),
+    NotFound(String),
+    /// The name is the start of several example names.
+    Ambiguous(String, Vec<String>),
+    /// Building the examples with cargo failed.
+    Build(ExitStatus),
+}
+
+impl fmt::Display for ExampleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExampleError::Io(e) => write!(f, "{}", e),
+            ExampleError::NotFound(name) => write!(f, "no example called `{}`", name),
+            ExampleError::Ambiguous(name, matches) => {
+                write!(f, "`{}` could be any of: {}", name, matches.join(", "))
+            }
+            ExampleError::Build(status) => write!(f, "building the examples failed: {}", status),
+        }
+    }
+}
+
+impl error::Error for ExampleError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ExampleError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ExampleError {
+    fn from(e: io::Error) -> Self {
+        ExampleError::Io(e)
+    }
+}
+
+/// One example binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Example {
+    /// Its position in the index, starting at 1.
+    pub number: usize,
+    /// The binary name, which is the file or directory name in `src/bin/`.
+    pub name: String,
+    /// The chapter from the name's numeric prefix, if it has one.
+    pub chapter: Option<u32>,
+    pub tag: Option<Tag>,
+}
+
+/// How a run of an example ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Exited(ExitStatus),
+    /// Killed after running longer than allowed.
+    TimedOut,
+}
+
+impl Status {
+    pub fn success(&self) -> bool {
+        matches!(self, Status::Exited(status) if status.success())
+    }
+
+    /// The exit code, if the example exited by itself.
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            Status::Exited(status) => status.code(),
+            Status::TimedOut => None,
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Exited(status) => match status.code() {
+                Some(code) => write!(f, "exit code {}", code),
+                None => write!(f, "{}", status),
+            },
+            Status::TimedOut => write!(f, "timed out"),
+        }
+    }
+}
+
+/// What an example printed, and how it ended.
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub status: Status,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration: Duration,
+}
+
+fn read_all<R: Read + Send + 'static>(reader: Option<R>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut reader) = reader {
+            // A read error just ends the output early.
+            let _ = reader.read_to_end(&mut bytes);
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+impl Example {
+    /// Runs the example's binary from `bin_dir` with `args`, in the
+    /// directory the examples expect (`src/bin/`, for their `../../data`
+    /// paths). Stdin is closed, so an interactive example sees end of
+    /// input. With a `timeout`, an example running longer is killed.
+    pub fn run(
+        &self,
+        bin_dir: &Path,
+        args: &[String],
+        timeout: Option<Duration>,
+    ) -> Result<Outcome, ExampleError> {
+        self.run_with(bin_dir, args, timeout, |_| {})
+    }
+
+    /// [`run`](Example::run), with `configure` called on the command
+    /// before it is spawned, e.g. to change the example's environment.
+    pub fn run_with(
+        &self,
+        bin_dir: &Path,
+        args: &[String],
+        timeout: Option<Duration>,
+        configure: impl FnOnce(&mut Command),
+    ) -> Result<Outcome, ExampleError> {
+        let program = bin_dir.join(format!("{}{}", self.name, env::consts::EXE_SUFFIX));
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .current_dir(ExampleIndex::DEFAULT_DIR)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        configure(&mut command);
+        let start = Instant::now();
+        let mut child = command.spawn()?;
+        // Read both pipes while waiting, so a chatty example cannot block.
+        let stdout = read_all(child.stdout.take());
+        let stderr = read_all(child.stderr.take());
+
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break Status::Exited(status);
+            }
+            if timeout.is_some_and(|timeout| start.elapsed() > timeout) {
+                child.kill()?;
+                child.wait()?;
+                break Status::TimedOut;
+            }
+            thread::sleep(Duration::from_millis(5));
+        };
+        Ok(Outcome {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+            duration: start.elapsed(),
+        })
+    }
+}
+
+/// Every example, sorted by name, which also sorts them by chapter.
+#[derive(Debug, Clone, Default)]
+pub struct ExampleIndex {
+    examples: Vec<Example>,
+}
+
+impl ExampleIndex {
+    /// Where the examples of this crate live.
+    pub const DEFAULT_DIR: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/bin");
+
+    /// Builds an index from the binary names cargo would find in `dir`:
+    /// every `.rs` file, and every directory with a `main.rs`.
+    pub fn discover<P: AsRef<Path>>(dir: P) -> Result<ExampleIndex, ExampleError> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = if path.is_dir() {
+                path.join("main.rs")
+                    .is_file()
+                    .then(|| path.file_name())
+                    .flatten()
+            } else if path.extension().is_some_and(|e| e == "rs") {
+                path.file_stem()
+            } else {
+                None
+            };
+            names.extend(name.and_then(|name| name.to_str()).map(str::to_string));
+        }
+        Ok(ExampleIndex::from_names(names))
+    }
+
+    pub fn from_names<I: IntoIterator<Item = String>>(names: I) -> ExampleIndex {
+        let mut names: Vec<String> = names.into_iter().collect();
+        // Examples without a chapter go last.
+        names.sort_by_key(|name| (chapter_of(name).is_none(), name.clone()));
+        let examples = names
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| Example {
+                number: index + 1,
+                chapter: chapter_of(&name),
+                tag: TAGGED.iter().find(|(n, _)| *n == name).map(|(_, tag)| *tag),
+                name,
+            })
+            .collect();
+        ExampleIndex { examples }
+    }
+
+    pub fn examples(&self) -> &[Example] {
+        &self.examples
+    }
+
+    /// Builds the examples with cargo if any of them is missing from
+    /// `bin_dir`, as after `cargo run`, which only builds the index.
+    pub fn ensure_built(&self, bin_dir: &Path) -> Result<(), ExampleError> {
+        let missing = self.examples.iter().any(|example| {
+            let program = format!("{}{}", example.name, env::consts::EXE_SUFFIX);
+            !bin_dir.join(program).is_file()
+        });
+        if !missing {
+            return Ok(());
+        }
+        let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+        let mut command = Command::new(cargo);
+        command
+            .args(["build", "--quiet", "--bins"])
+            .current_dir(env!("CARGO_MANIFEST_DIR"));
+        if bin_dir.ends_with("release") {
+            command.arg("--release");
+        }
+        let status = command.status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(ExampleError::Build(status))
+        }
+    }
+
+    /// Looks an example up by number, full name, or the start of a single
+    /// name.
+    ///
+    /// Numbers take precedence: `"20"` finds the twentieth example, even
+    /// if chapter 20 starts with `20`, and is only taken as the start of a
+    /// name when there are fewer examples. Write `"20_"` for the chapter.
+    pub fn find(&self, query: &str) -> Result<&Example, ExampleError> {
+        if let Ok(number) = query.parse::<usize>() {
+            if let Some(example) = self.examples.iter().find(|e| e.number == number) {
+                return Ok(example);
+            }
+        }
+        if let Some(example) = self.examples.iter().find(|e| e.name == query) {
+            return Ok(example);
+        }
+        let matches: Vec<&Example> = self
+            .examples
+            .iter()
+            .filter(|e| e.name.starts_with(query))
+            .collect();
+        match matches[..] {
+            [example] => Ok(example),
+            [] => Err(ExampleError::NotFound(query.to_string())),
+            _ => Err(ExampleError::Ambiguous(
+                query.to_string(),
+                matches.iter().map(|e| e.name.clone()).collect(),
+            )),
+        }
+    }
+}
+
+fn chapter_of(name: &str) -> Option<u32> {
+    let digits = name.split('_').next()?;
+    if digits.bytes().all(|b| b.is_ascii_digit()) {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+impl fmt::Display for ExampleIndex {
+    /// The examples, numbered, under a heading for each chapter.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut chapter = None;
+        for example in &self.examples {
+            if example.number == 1 || example.chapter != chapter {
+                chapter = example.chapter;
+                match chapter {
+                    Some(number) => writeln!(f, "{}. {}", number, chapter_title(number))?,
+                    None => writeln!(f, "Other")?,
+                }
+            }
+            write!(f, "  {:>3}  {}", example.number, example.name)?;
+            if let Some(tag) = example.tag {
+                write!(f, " ({})", tag)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of one example in a full run.
+#[derive(Debug)]
+pub enum Verdict {
+    Skipped(Tag),
+    Ran(Outcome),
+    Failed(ExampleError),
+}
+
+/// The results of running many examples.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub results: Vec<(Example, Verdict)>,
+}
+
+impl Summary {
+    fn count(&self, f: impl Fn(&Verdict) -> bool) -> usize {
+        self.results.iter().filter(|(_, v)| f(v)).count()
+    }
+
+    pub fn passed(&self) -> usize {
+        self.count(|v| matches!(v, Verdict::Ran(o) if o.status.success()))
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.count(|v| matches!(v, Verdict::Skipped(_)))
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed() - self.skipped()
+    }
+}
+
+impl fmt::Display for Summary {
+    /// A table with one row per example, then the totals.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .results
+            .iter()
+            .map(|(example, _)| example.name.len())
+            .max()
+            .unwrap_or(0);
+        writeln!(
+            f,
+            "{:>3}  {:<width$}  {:<7}  {:>8}  details",
+            "#", "example", "result", "time"
+        )?;
+        for (example, verdict) in &self.results {
+            write!(f, "{:>3}  {:<width$}  ", example.number, example.name)?;
+            match verdict {
+                Verdict::Skipped(tag) => writeln!(f, "{:<7}  {:>8}  {}", "skip", "-", tag)?,
+                Verdict::Failed(e) => writeln!(f, "{:<7}  {:>8}  {}", "FAIL", "-", e)?,
+                Verdict::Ran(outcome) => {
+                    let result = if outcome.status.success() {
+                        "ok"
+                    } else {
+                        "FAIL"
+                    };
+                    let millis = format!("{}ms", outcome.duration.as_millis());
+                    write!(f, "{:<7}  {:>8}", result, millis)?;
+                    if !outcome.status.success() {
+                        write!(f, "  {}", outcome.status)?;
+                    }
+                    writeln!(f)?;
+                }
+            }
+        }
+        writeln!(
+            f,
+            "{} passed, {} failed, {} skipped",
+            self.passed(),
+            self.failed(),
+            self.skipped()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> ExampleIndex {
+        ExampleIndex::from_names(
+            [
+                "fizzbuzz",
+                "14_generics_0",
+                "01_hello_world_2",
+                "20_std_misc_5_child_processes",
+                "01_hello_world_1",
+            ]
+            .map(String::from),
+        )
+    }
+
+    #[test]
+    fn test_index_groups_by_chapter() {
+        assert_eq!(
+            index().to_string(),
+            "1. Hello World\n    \
+               1  01_hello_world_1\n    \
+               2  01_hello_world_2\n\
+             14. Generics\n    \
+               3  14_generics_0\n\
+             20. Std Misc\n    \
+               4  20_std_misc_5_child_processes (slow)\n\
+             Other\n    \
+               5  fizzbuzz\n"
+        );
+    }
+
+    #[test]
+    fn test_find() {
+        let index = index();
+        assert_eq!(index.find("3").unwrap().name, "14_generics_0");
+        assert_eq!(index.find("fizzbuzz").unwrap().number, 5);
+        assert_eq!(index.find("20").unwrap().tag, Some(Tag::Slow));
+        assert_eq!(index.find("20_").unwrap().tag, Some(Tag::Slow));
+        assert_eq!(
+            index.find("01_hello").unwrap_err().to_string(),
+            "`01_hello` could be any of: 01_hello_world_1, 01_hello_world_2"
+        );
+        assert_eq!(
+            index.find("99").unwrap_err().to_string(),
+            "no example called `99`"
+        );
+        // Numbers come before names: example 2 is `a`, not `2_two`.
+        let index = ExampleIndex::from_names(["a", "2_two"].map(String::from));
+        assert_eq!(index.find("2").unwrap().name, "a");
+        assert_eq!(index.find("2_").unwrap().name, "2_two");
+    }
+
+    #[test]
+    fn test_discover_finds_every_binary() {
+        let index = ExampleIndex::discover(ExampleIndex::DEFAULT_DIR).unwrap();
+        let names: Vec<&str> = index.examples().iter().map(|e| e.name.as_str()).collect();
+        // A directory with a `main.rs` is a binary, one without is not.
+        assert!(names.contains(&"10_modules_file_hierarchy"));
+        assert!(!names.contains(&"11_crates"));
+        // The tools without a chapter number come last.
+        assert!(names.ends_with(&["calc", "fizzbuzz", "layout"]));
+        for (name, _) in TAGGED {
+            assert!(names.contains(&name), "{} is tagged but missing", name);
+        }
+    }
+}
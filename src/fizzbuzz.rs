@@ -0,0 +1,297 @@
+//! A configurable fizzbuzz.
+//!
+//! `while_loop` in `08_flow_of_control.rs` hard-codes three rules in an
+//! `if`/`else` chain. Here a [`Rules`] value holds an ordered list of
+//! `(condition, word)` pairs instead: every rule that matches a number adds
+//! its word, and a number that matches nothing is printed as itself. The
+//! classic game falls out of two rules, because 15 matches both `fizz` and
+//! `buzz`.
+//!
+//! ```
+//! use rust_by_example::fizzbuzz::Rules;
+//!
+//! let words: Vec<String> = Rules::classic().apply(13..=15).collect();
+//! assert_eq!(words, ["13", "14", "fizzbuzz"]);
+//! ```
+
+use std::{error, fmt, fs, io, path::Path};
+
+/// The test a number has to pass for a rule to fire.
+pub enum Condition {
+    /// The number is a multiple of the divisor.
+    Divisor(i64),
+    /// An arbitrary test, with a name used when displaying the rule.
+    Predicate(&'static str, Box<dyn Fn(i64) -> bool>),
+}
+
+impl Condition {
+    /// Returns whether `n` passes this condition.
+    pub fn matches(&self, n: i64) -> bool {
+        match self {
+            Condition::Divisor(divisor) => n.wrapping_rem(*divisor) == 0,
+            Condition::Predicate(_, predicate) => predicate(n),
+        }
+    }
+
+    // The predicates a rules file can refer to by name.
+    fn named(name: &str) -> Option<Condition> {
+        let (name, predicate): (_, Box<dyn Fn(i64) -> bool>) = match name {
+            "even" => ("even", Box::new(|n| n % 2 == 0)),
+            "odd" => ("odd", Box::new(|n| n % 2 != 0)),
+            "prime" => ("prime", Box::new(is_prime)),
+            "square" => ("square", Box::new(is_square)),
+            _ => return None,
+        };
+        Some(Condition::Predicate(name, predicate))
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Divisor(divisor) => write!(f, "{}", divisor),
+            Condition::Predicate(name, _) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl fmt::Debug for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Divisor(divisor) => f.debug_tuple("Divisor").field(divisor).finish(),
+            Condition::Predicate(name, _) => f.debug_tuple("Predicate").field(name).finish(),
+        }
+    }
+}
+
+// `d <= n / d` rather than `d * d <= n`, which overflows near `i64::MAX`.
+fn is_prime(n: i64) -> bool {
+    n >= 2 && (2..).take_while(|d| *d <= n / d).all(|d| n % d != 0)
+}
+
+fn is_square(n: i64) -> bool {
+    n >= 0 && n.isqrt().pow(2) == n
+}
+
+/// A single `(condition, word)` pair.
+#[derive(Debug)]
+pub struct Rule {
+    pub condition: Condition,
+    pub word: String,
+}
+
+/// An ordered list of rules.
+#[derive(Debug, Default)]
+pub struct Rules {
+    rules: Vec<Rule>,
+}
+
+/// The errors that can occur while loading a rules file.
+#[derive(Debug)]
+pub enum RulesError {
+    Io(io::Error),
+    /// A line that is neither `<divisor> <word>` nor `<predicate> <word>`.
+    Syntax {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RulesError::Io(e) => write!(f, "could not read rules: {}", e),
+            RulesError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl error::Error for RulesError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            RulesError::Io(e) => Some(e),
+            RulesError::Syntax { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for RulesError {
+    fn from(e: io::Error) -> Self {
+        RulesError::Io(e)
+    }
+}
+
+impl Rules {
+    /// Creates an empty rule set, which prints every number as itself.
+    pub fn new() -> Rules {
+        Rules::default()
+    }
+
+    /// The rules of `while_loop`: "fizz" on 3, "buzz" on 5.
+    pub fn classic() -> Rules {
+        Rules::new().divisor(3, "fizz").divisor(5, "buzz")
+    }
+
+    /// Adds a rule that fires on multiples of `divisor`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `divisor` is zero.
+    pub fn divisor(self, divisor: i64, word: &str) -> Rules {
+        assert!(divisor != 0, "divisor must not be zero");
+        self.rule(Condition::Divisor(divisor), word)
+    }
+
+    /// Adds a rule that fires whenever `predicate` returns `true`.
+    pub fn predicate<F>(self, name: &'static str, predicate: F, word: &str) -> Rules
+    where
+        F: Fn(i64) -> bool + 'static,
+    {
+        self.rule(Condition::Predicate(name, Box::new(predicate)), word)
+    }
+
+    fn rule(mut self, condition: Condition, word: &str) -> Rules {
+        self.rules.push(Rule {
+            condition,
+            word: word.to_string(),
+        });
+        self
+    }
+
+    /// The rules, in the order they are applied.
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Parses rules from text, one `<divisor|predicate> <word>` per line.
+    ///
+    /// Blank lines and lines starting with `#` are ignored. The predicates
+    /// that can be named are `even`, `odd`, `prime` and `square`.
+    ///
+    /// ```
+    /// use rust_by_example::fizzbuzz::Rules;
+    ///
+    /// let rules = Rules::parse("# primes are special\nprime zap\n2 even\n").unwrap();
+    /// assert_eq!(rules.word_for(2), "zapeven");
+    /// assert_eq!(rules.word_for(9), "9");
+    /// ```
+    pub fn parse(text: &str) -> Result<Rules, RulesError> {
+        let mut rules = Rules::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let syntax = |message: String| RulesError::Syntax {
+                line: index + 1,
+                message,
+            };
+
+            let mut fields = line.split_whitespace();
+            let (Some(condition), Some(word), None) = (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(syntax(format!(
+                    "expected `<condition> <word>`, got `{}`",
+                    line
+                )));
+            };
+            let condition = match condition.parse::<i64>() {
+                Ok(0) => return Err(syntax("divisor must not be zero".to_string())),
+                Ok(divisor) => Condition::Divisor(divisor),
+                Err(_) => Condition::named(condition)
+                    .ok_or_else(|| syntax(format!("unknown predicate `{}`", condition)))?,
+            };
+            rules = rules.rule(condition, word);
+        }
+        Ok(rules)
+    }
+
+    /// Reads and parses a rules file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Rules, RulesError> {
+        Rules::parse(&fs::read_to_string(path)?)
+    }
+
+    /// The output for a single number.
+    pub fn word_for(&self, n: i64) -> String {
+        let words: String = self
+            .rules
+            .iter()
+            .filter(|rule| rule.condition.matches(n))
+            .map(|rule| rule.word.as_str())
+            .collect();
+        if words.is_empty() {
+            n.to_string()
+        } else {
+            words
+        }
+    }
+
+    /// Lazily maps every number in `numbers` to its output.
+    ///
+    /// `numbers` can be any iterator, including unbounded ranges like `1..`.
+    pub fn apply<'a, I>(&'a self, numbers: I) -> impl Iterator<Item = String> + 'a
+    where
+        I: IntoIterator<Item = i64>,
+        I::IntoIter: 'a,
+    {
+        numbers.into_iter().map(move |n| self.word_for(n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_concatenate_in_order() {
+        let rules = Rules::new().divisor(5, "buzz").divisor(3, "fizz");
+        assert_eq!(rules.word_for(15), "buzzfizz");
+        assert_eq!(Rules::classic().word_for(15), "fizzbuzz");
+        assert_eq!(Rules::classic().word_for(-9), "fizz");
+        assert_eq!(Rules::new().word_for(7), "7");
+    }
+
+    #[test]
+    fn test_predicates() {
+        let rules = Rules::new()
+            .predicate("negative", |n| n < 0, "neg")
+            .divisor(2, "even");
+        let words: Vec<String> = rules.apply(-2..=1).collect();
+        assert_eq!(words, ["negeven", "neg", "even", "1"]);
+        assert!((1..30)
+            .filter(|&n| is_prime(n))
+            .eq([2, 3, 5, 7, 11, 13, 17, 19, 23, 29]));
+        assert!((0..30).filter(|&n| is_square(n)).eq([0, 1, 4, 9, 16, 25]));
+
+        // Near the end of the range, where squaring overflows.
+        let root = i64::MAX.isqrt();
+        assert!(is_square(root * root) && !is_square(i64::MAX));
+        assert!(!is_prime(i64::MAX));
+        assert!(is_prime(2_147_483_647));
+        let words: Vec<String> = Rules::new()
+            .predicate("prime", is_prime, "prime")
+            .predicate("square", is_square, "square")
+            .apply(i64::MAX - 1..=i64::MAX)
+            .collect();
+        assert_eq!(words, [(i64::MAX - 1).to_string(), i64::MAX.to_string()]);
+    }
+
+    #[test]
+    fn test_apply_is_lazy() {
+        let rules = Rules::classic();
+        let tenth_fizzbuzz = rules.apply(1..).filter(|w| w == "fizzbuzz").nth(9);
+        assert_eq!(tenth_fizzbuzz.as_deref(), Some("fizzbuzz"));
+        let words: Vec<String> = rules.apply((0..100).step_by(25)).collect();
+        assert_eq!(words, ["fizzbuzz", "buzz", "buzz", "fizzbuzz"]);
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_line() {
+        let err = Rules::parse("3 fizz\n\n5\n").unwrap_err();
+        assert!(matches!(err, RulesError::Syntax { line: 3, .. }), "{err}");
+        let err = Rules::parse("# zero\n0 never\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: divisor must not be zero");
+        let err = Rules::parse("fibonacci fib").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: unknown predicate `fibonacci`");
+    }
+}
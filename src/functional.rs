@@ -0,0 +1,313 @@
+//! Combinators for building functions out of other functions.
+//!
+//! `make_adder_function` in `16_traits_6_impl_trait.rs` returns an
+//! `impl Fn(i32) -> i32`. The functions here take and return closures in
+//! the same way: [`compose`] and [`pipe!`](crate::pipe) chain them,
+//! [`partial`] fixes an argument, and [`memoize`] and [`memoize_recursive`]
+//! remember results so that each input is only computed once.
+//!
+//! ```
+//! use rust_by_example::{functional::partial, pipe};
+//!
+//! let add = |a: i32, b: i32| a + b;
+//! let plus_one = partial(add, 1);
+//! let f = pipe!(plus_one, |x| x * 10, |x: i32| x.to_string());
+//! assert_eq!(f(4), "50");
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+};
+
+/// Returns the function `x -> f(g(x))`, which applies `g` first.
+///
+/// ```
+/// use rust_by_example::functional::compose;
+///
+/// let double_then_negate = compose(|x: i32| -x, |x: i32| x * 2);
+/// assert_eq!(double_then_negate(3), -6);
+/// ```
+pub fn compose<A, B, C>(f: impl Fn(B) -> C, g: impl Fn(A) -> B) -> impl Fn(A) -> C {
+    move |x| f(g(x))
+}
+
+/// Chains any number of functions left to right: `pipe!(f, g, h)` is the
+/// function `x -> h(g(f(x)))`.
+#[macro_export]
+macro_rules! pipe {
+    ($f:expr $(,)?) => {
+        $f
+    };
+    ($f:expr, $($rest:expr),+ $(,)?) => {
+        $crate::functional::compose($crate::pipe!($($rest),+), $f)
+    };
+}
+
+/// Fixes the first argument of a two-argument function.
+pub fn partial<A: Clone, B, C>(f: impl Fn(A, B) -> C, a: A) -> impl Fn(B) -> C {
+    move |b| f(a.clone(), b)
+}
+
+/// Fixes the last argument of a two-argument function.
+pub fn partial_last<A, B: Clone, C>(f: impl Fn(A, B) -> C, b: B) -> impl Fn(A) -> C {
+    move |a| f(a, b.clone())
+}
+
+/// Results keyed by argument, optionally evicting the least recently used
+/// entry once `capacity` is reached.
+struct Cache<A, R> {
+    /// Each result, with the time it was last used.
+    entries: HashMap<A, (R, u64)>,
+    /// The arguments by the time they were last used.
+    recency: BTreeMap<u64, A>,
+    capacity: Option<usize>,
+    clock: u64,
+}
+
+impl<A: Hash + Eq + Clone, R: Clone> Cache<A, R> {
+    fn new(capacity: Option<usize>) -> Cache<A, R> {
+        Cache {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            capacity,
+            clock: 0,
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn get(&mut self, arg: &A) -> Option<R> {
+        let now = self.tick();
+        let (result, used) = self.entries.get_mut(arg)?;
+        let arg = self
+            .recency
+            .remove(used)
+            .expect("every entry has a use time");
+        *used = now;
+        let result = result.clone();
+        self.recency.insert(now, arg);
+        Some(result)
+    }
+
+    fn insert(&mut self, arg: A, result: R) {
+        if self.capacity == Some(0) {
+            return;
+        }
+        if let Some((_, used)) = self.entries.remove(&arg) {
+            self.recency.remove(&used);
+        }
+        if self
+            .capacity
+            .is_some_and(|capacity| self.entries.len() >= capacity)
+        {
+            if let Some((_, oldest)) = self.recency.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        let now = self.tick();
+        self.recency.insert(now, arg.clone());
+        self.entries.insert(arg, (result, now));
+    }
+}
+
+/// A function that remembers its results. Created by [`memoize`].
+pub struct Memo<A, R, F> {
+    f: F,
+    cache: Cache<A, R>,
+}
+
+/// Wraps `f` so that it runs at most once per argument.
+///
+/// ```
+/// use rust_by_example::functional::memoize;
+///
+/// let mut square = memoize(|x: u64| {
+///     println!("computing {}^2", x);
+///     x * x
+/// });
+/// assert_eq!(square.call(12), 144);
+/// assert_eq!(square.call(12), 144); // Printed nothing this time.
+/// ```
+pub fn memoize<A, R, F>(f: F) -> Memo<A, R, F>
+where
+    A: Hash + Eq + Clone,
+    R: Clone,
+    F: FnMut(A) -> R,
+{
+    Memo {
+        f,
+        cache: Cache::new(None),
+    }
+}
+
+impl<A, R, F> Memo<A, R, F>
+where
+    A: Hash + Eq + Clone,
+    R: Clone,
+    F: FnMut(A) -> R,
+{
+    /// Keeps only the `capacity` most recently used results.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.cache = Cache::new(Some(capacity));
+        self
+    }
+
+    pub fn call(&mut self, arg: A) -> R {
+        if let Some(result) = self.cache.get(&arg) {
+            return result;
+        }
+        let result = (self.f)(arg.clone());
+        self.cache.insert(arg, result.clone());
+        result
+    }
+
+    /// The number of remembered results.
+    pub fn len(&self) -> usize {
+        self.cache.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.cache = Cache::new(self.cache.capacity);
+    }
+}
+
+fn call_recursive<A, R, F>(cache: &mut Cache<A, R>, f: &F, arg: A) -> R
+where
+    A: Hash + Eq + Clone,
+    R: Clone,
+    F: Fn(&mut dyn FnMut(A) -> R, A) -> R,
+{
+    if let Some(result) = cache.get(&arg) {
+        return result;
+    }
+    let result = f(&mut |a| call_recursive(cache, f, a), arg.clone());
+    cache.insert(arg, result.clone());
+    result
+}
+
+/// Memoizes a recursive function. `f` receives the memoized function
+/// itself as its first argument and makes its recursive calls through it,
+/// so those are cached too.
+///
+/// ```
+/// use rust_by_example::functional::memoize_recursive;
+///
+/// let mut fibonacci = memoize_recursive(|fib, n: u64| {
+///     if n < 2 {
+///         n
+///     } else {
+///         fib(n - 1) + fib(n - 2)
+///     }
+/// });
+/// assert_eq!(fibonacci(90), 2_880_067_194_370_816_120);
+/// ```
+pub fn memoize_recursive<A, R, F>(f: F) -> impl FnMut(A) -> R
+where
+    A: Hash + Eq + Clone,
+    R: Clone,
+    F: Fn(&mut dyn FnMut(A) -> R, A) -> R,
+{
+    let mut cache = Cache::new(None);
+    move |arg| call_recursive(&mut cache, &f, arg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_compose_and_pipe() {
+        let add_one = |x: i32| x + 1;
+        let double = |x: i32| x * 2;
+        assert_eq!(compose(add_one, double)(5), 11);
+        assert_eq!(compose(double, add_one)(5), 12);
+        assert_eq!(pipe!(add_one)(5), 6);
+        assert_eq!(pipe!(add_one, double, add_one,)(5), 13);
+        let cells = |n: usize| format!("{} cells", n);
+        let describe = pipe!(|s: &str| s.len(), |n| n * n, cells);
+        assert_eq!(describe("abc"), "9 cells");
+    }
+
+    #[test]
+    fn test_partial() {
+        let greet = |greeting: &str, name: &str| format!("{}, {}!", greeting, name);
+        assert_eq!(partial(greet, "Hello")("Ferris"), "Hello, Ferris!");
+        assert_eq!(partial_last(greet, "Ferris")("Bye"), "Bye, Ferris!");
+        let subtract = |a: i32, b: i32| a - b;
+        assert_eq!(partial(subtract, 10)(3), 7);
+        assert_eq!(partial_last(subtract, 10)(3), -7);
+    }
+
+    #[test]
+    fn test_memoize_runs_once_per_key() {
+        let calls = Cell::new(0);
+        let mut length = memoize(|s: String| {
+            calls.set(calls.get() + 1);
+            s.len()
+        });
+        for word in ["apple", "pear", "apple", "apple", "pear", "fig"] {
+            assert_eq!(length.call(word.to_string()), word.len());
+        }
+        assert_eq!(calls.get(), 3);
+        assert_eq!(length.len(), 3);
+        length.clear();
+        length.call("apple".to_string());
+        assert_eq!(calls.get(), 4);
+    }
+
+    #[test]
+    fn test_memoize_lru_evicts_least_recently_used() {
+        let calls = Cell::new(0);
+        let mut square = memoize(|x: u32| {
+            calls.set(calls.get() + 1);
+            x * x
+        })
+        .with_capacity(2);
+        square.call(1);
+        square.call(2);
+        // Using 1 again makes 2 the least recently used entry.
+        square.call(1);
+        square.call(3);
+        assert_eq!((square.len(), calls.get()), (2, 3));
+        square.call(1);
+        assert_eq!(calls.get(), 3);
+        square.call(2);
+        assert_eq!(calls.get(), 4);
+
+        let mut uncached = memoize(|x: u32| {
+            calls.set(calls.get() + 1);
+            x
+        })
+        .with_capacity(0);
+        uncached.call(7);
+        uncached.call(7);
+        assert_eq!((uncached.len(), calls.get()), (0, 6));
+    }
+
+    #[test]
+    fn test_memoize_recursive() {
+        let calls = Cell::new(0);
+        let mut fibonacci = memoize_recursive(|fib, n: u64| {
+            calls.set(calls.get() + 1);
+            if n < 2 {
+                n
+            } else {
+                fib(n - 1) + fib(n - 2)
+            }
+        });
+        assert_eq!(fibonacci(30), 832_040);
+        // Each of 0..=30 is computed exactly once.
+        assert_eq!(calls.get(), 31);
+        assert_eq!(fibonacci(31), 1_346_269);
+        assert_eq!(calls.get(), 32);
+    }
+}
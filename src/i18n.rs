@@ -0,0 +1,395 @@
+//! A small message catalog for localized output.
+//!
+//! Messages live in one file per locale under `data/locales/`, one
+//! `key = message` per line. Messages can contain named placeholders such as
+//! `{name}`, and plural messages have one key per plural category, e.g.
+//! `contacts.count.one` and `contacts.count.other`. Anything missing from a
+//! locale falls back to English, and anything missing from English falls
+//! back to the key itself.
+//!
+//! ```
+//! use rust_by_example::i18n::Catalog;
+//!
+//! let german = Catalog::load(Catalog::DEFAULT_DIR, "de_DE.UTF-8")?;
+//! assert_eq!(german.locale(), "de");
+//! assert_eq!(german.get("hello.person", &[("name", &"Ferris")]), "Hallo, Ferris!");
+//! assert_eq!(german.plural("contacts.count", 2, &[]), "2 Kontakte");
+//! # Ok::<(), rust_by_example::i18n::CatalogError>(())
+//! ```
+
+use std::{
+    collections::HashMap,
+    env, error,
+    fmt::{self, Display},
+    fs, io,
+    path::Path,
+    sync::OnceLock,
+};
+
+/// The errors that can occur while loading a locale file.
+#[derive(Debug)]
+pub enum CatalogError {
+    Io(io::Error),
+    Syntax { line: usize, text: String },
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogError::Io(e) => write!(f, "could not read messages: {}", e),
+            CatalogError::Syntax { line, text } => {
+                write!(f, "line {}: expected `key = message`, got `{}`", line, text)
+            }
+        }
+    }
+}
+
+impl error::Error for CatalogError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CatalogError::Io(e) => Some(e),
+            CatalogError::Syntax { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for CatalogError {
+    fn from(e: io::Error) -> Self {
+        CatalogError::Io(e)
+    }
+}
+
+/// The CLDR plural categories used by the supported languages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluralCategory {
+    One,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    /// The category `count` falls into in `language`, e.g. `"pl"`.
+    /// Languages without a specific rule use the English one.
+    pub fn of(language: &str, count: u64) -> PluralCategory {
+        match language {
+            "fr" => match count {
+                0 | 1 => PluralCategory::One,
+                _ => PluralCategory::Other,
+            },
+            "pl" => match (count, count % 10, count % 100) {
+                (1, _, _) => PluralCategory::One,
+                (_, 2..=4, tens) if !(12..=14).contains(&tens) => PluralCategory::Few,
+                _ => PluralCategory::Many,
+            },
+            _ => match count {
+                1 => PluralCategory::One,
+                _ => PluralCategory::Other,
+            },
+        }
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            PluralCategory::One => "one",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+}
+
+/// Picks the locale from `LC_ALL` or, failing that, `LANG`, the way the C
+/// library does. Returns `None` for unset, empty, `C` and `POSIX` values.
+pub fn locale_from(lc_all: Option<&str>, lang: Option<&str>) -> Option<String> {
+    [lc_all, lang]
+        .into_iter()
+        .flatten()
+        .find(|value| !value.is_empty())
+        .filter(|value| !matches!(*value, "C" | "POSIX") && !value.starts_with("C."))
+        .map(|value| value.to_string())
+}
+
+// Splits a locale such as `de_DE.UTF-8@euro` into the file names to try, most
+// specific first: `de_DE`, `de`.
+fn candidates(locale: &str) -> Vec<String> {
+    let base = locale.split(['.', '@']).next().unwrap_or(locale);
+    let mut names = vec![base.to_string()];
+    if let Some((language, _)) = base.split_once(['_', '-']) {
+        names.push(language.to_string());
+    }
+    names
+}
+
+/// The messages of one locale, with English as the fallback.
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    locale: String,
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// Where the locale files of this repository live.
+    pub const DEFAULT_DIR: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/data/locales");
+
+    /// Parses `key = message` lines. Blank lines and lines starting with
+    /// `#` are ignored.
+    pub fn parse(locale: &str, text: &str) -> Result<Catalog, CatalogError> {
+        let mut messages = HashMap::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, message) = line.split_once('=').ok_or_else(|| CatalogError::Syntax {
+                line: index + 1,
+                text: line.to_string(),
+            })?;
+            messages.insert(key.trim().to_string(), message.trim().to_string());
+        }
+        Ok(Catalog {
+            locale: locale.to_string(),
+            messages,
+        })
+    }
+
+    /// The English catalog, compiled into the binary so it is always there.
+    pub fn english() -> &'static Catalog {
+        static ENGLISH: OnceLock<Catalog> = OnceLock::new();
+        ENGLISH.get_or_init(|| {
+            Catalog::parse("en", include_str!("../data/locales/en.txt"))
+                .expect("the English catalog is valid")
+        })
+    }
+
+    /// Loads the file for `locale` from `dir`, trying e.g. `de_DE.txt`
+    /// before `de.txt`. An unknown locale gives an empty catalog, so every
+    /// lookup falls back to English.
+    pub fn load<P: AsRef<Path>>(dir: P, locale: &str) -> Result<Catalog, CatalogError> {
+        for name in candidates(locale) {
+            match fs::read_to_string(dir.as_ref().join(format!("{}.txt", name))) {
+                Ok(text) => return Catalog::parse(&name, &text),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Catalog::parse("en", "")
+    }
+
+    /// The catalog for the locale in `LC_ALL` or `LANG`, loaded from
+    /// [`DEFAULT_DIR`](Catalog::DEFAULT_DIR). Falls back to English when
+    /// neither is set or the file cannot be read.
+    pub fn from_env() -> Catalog {
+        let lc_all = env::var("LC_ALL").ok();
+        let lang = env::var("LANG").ok();
+        locale_from(lc_all.as_deref(), lang.as_deref())
+            .and_then(|locale| Catalog::load(Catalog::DEFAULT_DIR, &locale).ok())
+            .unwrap_or_else(|| Catalog::english().clone())
+    }
+
+    /// The locale whose file was loaded, e.g. `de`.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    fn lookup(&self, key: &str) -> Option<&str> {
+        self.messages
+            .get(key)
+            .or_else(|| Catalog::english().messages.get(key))
+            .map(String::as_str)
+    }
+
+    /// The message for `key` with its placeholders filled in from `args`.
+    /// Unknown keys come back as the key itself.
+    pub fn get(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        match self.lookup(key) {
+            Some(template) => format_message(template, args),
+            None => key.to_string(),
+        }
+    }
+
+    /// The plural form of `key` for `count`, chosen by the rules of this
+    /// locale's language. `{count}` is available as a placeholder. Unknown
+    /// keys come back as the key itself.
+    ///
+    /// ```
+    /// use rust_by_example::i18n::Catalog;
+    ///
+    /// let polish = Catalog::load(Catalog::DEFAULT_DIR, "pl_PL")?;
+    /// assert_eq!(polish.plural("contacts.count", 3, &[]), "3 kontakty");
+    /// assert_eq!(polish.plural("contacts.count", 12, &[]), "12 kontaktów");
+    /// # Ok::<(), rust_by_example::i18n::CatalogError>(())
+    /// ```
+    pub fn plural(&self, key: &str, count: u64, args: &[(&str, &dyn Display)]) -> String {
+        // The least specific candidate is the language, e.g. `pl` of `pl-PL`.
+        let language = candidates(&self.locale).pop().unwrap_or_default();
+        let category = PluralCategory::of(&language, count);
+        let mut all_args: Vec<(&str, &dyn Display)> = vec![("count", &count)];
+        all_args.extend_from_slice(args);
+
+        let specific = format!("{}.{}", key, category.suffix());
+        let other = format!("{}.other", key);
+        match self.messages.get(&specific).or(self.messages.get(&other)) {
+            Some(template) => format_message(template, &all_args),
+            // Fall back to English as a whole, so its own plural rule applies.
+            None if self.locale != "en" => Catalog::english().plural(key, count, args),
+            None => key.to_string(),
+        }
+    }
+}
+
+// Replaces every `{name}` in `template` with the matching argument. `{{`
+// and `}}` stand for literal braces; unknown placeholders are kept as is.
+fn format_message(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            out.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+        let placeholder = rest
+            .strip_prefix('{')
+            .and_then(|after| after.split_once('}'))
+            .and_then(|(name, after)| {
+                let (_, value) = args.iter().find(|(arg, _)| *arg == name)?;
+                Some((value, after))
+            });
+        match placeholder {
+            Some((value, after)) => {
+                out.push_str(&value.to_string());
+                rest = after;
+            }
+            None => {
+                out.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog(locale: &str) -> Catalog {
+        Catalog::load(Catalog::DEFAULT_DIR, locale).unwrap()
+    }
+
+    #[test]
+    fn test_greetings_in_every_locale() {
+        let greetings: Vec<_> = ["en_US.UTF-8", "de_DE", "fr_FR.UTF-8", "pl"]
+            .into_iter()
+            .map(|locale| catalog(locale).get("hello.person", &[("name", &"Ferris")]))
+            .collect();
+        assert_eq!(
+            greetings,
+            [
+                "Hello, Ferris!",
+                "Hallo, Ferris!",
+                "Bonjour, Ferris !",
+                "Witaj, Ferris!"
+            ]
+        );
+        assert_eq!(catalog("fr").get("hello.world", &[]), "Bonjour, le monde !");
+    }
+
+    #[test]
+    fn test_plural_rules() {
+        let counts = [0, 1, 2, 5, 12, 22, 25];
+        let forms = |locale: &str| -> Vec<String> {
+            let catalog = catalog(locale);
+            counts
+                .iter()
+                .map(|&n| catalog.plural("contacts.count", n, &[]))
+                .collect()
+        };
+        assert_eq!(
+            forms("en"),
+            [
+                "0 contacts",
+                "1 contact",
+                "2 contacts",
+                "5 contacts",
+                "12 contacts",
+                "22 contacts",
+                "25 contacts"
+            ]
+        );
+        assert_eq!(forms("fr")[..3], ["0 contact", "1 contact", "2 contacts"]);
+        assert_eq!(
+            forms("pl"),
+            [
+                "0 kontaktów",
+                "1 kontakt",
+                "2 kontakty",
+                "5 kontaktów",
+                "12 kontaktów",
+                "22 kontakty",
+                "25 kontaktów"
+            ]
+        );
+        // A locale with a region still uses the rules of its language.
+        let polish = Catalog::parse(
+            "pl-PL",
+            "contacts.count.few = {count} kontakty\ncontacts.count.many = {count} kontaktów",
+        )
+        .unwrap();
+        assert_eq!(polish.plural("contacts.count", 22, &[]), "22 kontakty");
+        assert_eq!(polish.plural("contacts.count", 25, &[]), "25 kontaktów");
+    }
+
+    #[test]
+    fn test_missing_keys_fall_back() {
+        let german = Catalog::parse("de", "hello.world = Hallo, Welt!").unwrap();
+        assert_eq!(
+            german.get("hello.person", &[("name", &"Ferris")]),
+            "Hello, Ferris!"
+        );
+        assert_eq!(german.plural("contacts.count", 1, &[]), "1 contact");
+        assert_eq!(german.get("no.such.key", &[]), "no.such.key");
+        assert_eq!(german.plural("no.such.key", 2, &[]), "no.such.key");
+
+        let unknown = catalog("tlh_QO");
+        assert_eq!(unknown.locale(), "en");
+        assert_eq!(unknown.get("hello.world", &[]), "Hello, world!");
+    }
+
+    #[test]
+    fn test_locale_from_env_values() {
+        assert_eq!(
+            locale_from(Some("fr_FR"), Some("de_DE")).as_deref(),
+            Some("fr_FR")
+        );
+        assert_eq!(
+            locale_from(Some(""), Some("de_DE")).as_deref(),
+            Some("de_DE")
+        );
+        assert_eq!(locale_from(None, Some("C.UTF-8")), None);
+        assert_eq!(locale_from(Some("POSIX"), Some("de_DE")), None);
+        assert_eq!(locale_from(None, None), None);
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let args: [(&str, &dyn Display); 2] = [("a", &1), ("b", &"two")];
+        assert_eq!(format_message("{a} and {b}", &args), "1 and two");
+        assert_eq!(format_message("{{a}} is {a}", &args), "{a} is 1");
+        assert_eq!(format_message("{c} {a", &args), "{c} {a");
+        assert_eq!(format_message("ünïcödé {b}", &args), "ünïcödé two");
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let error = Catalog::parse("en", "# comment\nhello.world Hello").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: expected `key = message`, got `hello.world Hello`"
+        );
+    }
+}
@@ -0,0 +1,397 @@
+//! Intervals and sets of intervals over any ordered type.
+//!
+//! `Container(i32, i32)` in `14_generics_8_associated_items.rs` and
+//! `MinMax(i64, i64)` in `01_hello_world_1.rs` are both bare pairs of
+//! bounds. [`Interval<T>`] is a checked, half-open range `[start, end)`, and
+//! [`IntervalSet<T>`] keeps a sorted list of disjoint intervals, merging
+//! any that overlap or touch.
+//!
+//! ```
+//! use rust_by_example::interval::{Interval, IntervalSet};
+//!
+//! let mut working_hours: IntervalSet<u32> = IntervalSet::new();
+//! working_hours.insert(Interval::new(9, 12).unwrap());
+//! working_hours.insert(Interval::new(13, 17).unwrap());
+//! working_hours.insert(Interval::new(12, 13).unwrap());
+//! assert_eq!(working_hours.to_string(), "{[9, 17)}");
+//!
+//! let meetings: IntervalSet<u32> = [(10, 11), (16, 18)]
+//!     .into_iter()
+//!     .filter_map(|(start, end)| Interval::new(start, end))
+//!     .collect();
+//! let free = working_hours.difference(&meetings);
+//! assert_eq!(free.to_string(), "{[9, 10), [11, 16)}");
+//! ```
+
+use std::{cmp, fmt};
+
+/// A generalization of the `Contains` trait from
+/// `14_generics_8_associated_items.rs`: whether both values are in the
+/// container, and its smallest and largest bounds.
+pub trait Contains {
+    type A;
+    type B;
+
+    fn contains(&self, _: &Self::A, _: &Self::B) -> bool;
+    fn first(&self) -> Option<&Self::A>;
+    fn last(&self) -> Option<&Self::B>;
+}
+
+/// The half-open range `[start, end)`. It is never empty.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Interval<T> {
+    start: T,
+    end: T,
+}
+
+impl<T: Ord> Interval<T> {
+    /// Returns `None` unless `start < end`, since the interval would be empty.
+    pub fn new(start: T, end: T) -> Option<Interval<T>> {
+        if start < end {
+            Some(Interval { start, end })
+        } else {
+            None
+        }
+    }
+
+    pub fn start(&self) -> &T {
+        &self.start
+    }
+
+    /// The first value after the interval.
+    pub fn end(&self) -> &T {
+        &self.end
+    }
+
+    pub fn contains_point(&self, point: &T) -> bool {
+        self.start <= *point && *point < self.end
+    }
+
+    pub fn contains_interval(&self, other: &Interval<T>) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Whether the two intervals share at least one point.
+    pub fn overlaps(&self, other: &Interval<T>) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+
+    /// Whether the two intervals overlap or are directly adjacent, so that
+    /// their union is a single interval.
+    pub fn touches(&self, other: &Interval<T>) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+impl<T: Ord + Clone> Interval<T> {
+    /// The points in both intervals, if there are any.
+    pub fn intersection(&self, other: &Interval<T>) -> Option<Interval<T>> {
+        Interval::new(
+            cmp::max(&self.start, &other.start).clone(),
+            cmp::min(&self.end, &other.end).clone(),
+        )
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Interval<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {})", self.start, self.end)
+    }
+}
+
+impl<T: Ord> Contains for Interval<T> {
+    type A = T;
+    type B = T;
+
+    fn contains(&self, a: &T, b: &T) -> bool {
+        self.contains_point(a) && self.contains_point(b)
+    }
+
+    fn first(&self) -> Option<&T> {
+        Some(&self.start)
+    }
+
+    fn last(&self) -> Option<&T> {
+        Some(&self.end)
+    }
+}
+
+/// A set of values, stored as sorted intervals that neither overlap nor
+/// touch.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet {
+            intervals: Vec::new(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> IntervalSet<T> {
+    pub fn new() -> IntervalSet<T> {
+        IntervalSet::default()
+    }
+
+    /// The intervals of the set, in increasing order.
+    pub fn intervals(&self) -> &[Interval<T>] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Adds `interval` to the set, merging it with every interval it touches.
+    pub fn insert(&mut self, interval: Interval<T>) {
+        let Interval { mut start, mut end } = interval;
+        let mut merged = Vec::with_capacity(self.intervals.len() + 1);
+        let mut placed = false;
+        for current in self.intervals.drain(..) {
+            if current.end < start {
+                merged.push(current);
+            } else if end < current.start {
+                if !placed {
+                    merged.push(Interval {
+                        start: start.clone(),
+                        end: end.clone(),
+                    });
+                    placed = true;
+                }
+                merged.push(current);
+            } else {
+                start = cmp::min(start, current.start);
+                end = cmp::max(end, current.end);
+            }
+        }
+        if !placed {
+            merged.push(Interval { start, end });
+        }
+        self.intervals = merged;
+    }
+
+    /// The only interval that could contain `point`.
+    fn candidate(&self, point: &T) -> Option<&Interval<T>> {
+        let index = self.intervals.partition_point(|i| i.end <= *point);
+        self.intervals.get(index)
+    }
+
+    pub fn contains_point(&self, point: &T) -> bool {
+        self.candidate(point)
+            .is_some_and(|i| i.contains_point(point))
+    }
+
+    /// Whether every point of `interval` is in the set.
+    pub fn contains_interval(&self, interval: &Interval<T>) -> bool {
+        // Intervals in the set never touch, so one of them must hold it all.
+        self.candidate(&interval.start)
+            .is_some_and(|i| i.contains_interval(interval))
+    }
+
+    /// The points in either set.
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut union = self.clone();
+        for interval in &other.intervals {
+            union.insert(interval.clone());
+        }
+        union
+    }
+
+    /// The points in both sets.
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let (mut i, mut j) = (0, 0);
+        let mut intervals = Vec::new();
+        while let (Some(a), Some(b)) = (self.intervals.get(i), other.intervals.get(j)) {
+            intervals.extend(a.intersection(b));
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { intervals }
+    }
+
+    /// The points in `self` but not in `other`.
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut intervals = Vec::new();
+        let mut j = 0;
+        for a in &self.intervals {
+            while other.intervals.get(j).is_some_and(|b| b.end <= a.start) {
+                j += 1;
+            }
+            let mut start = a.start.clone();
+            for b in other.intervals[j..].iter().take_while(|b| b.start < a.end) {
+                if start < b.start {
+                    intervals.push(Interval {
+                        start,
+                        end: b.start.clone(),
+                    });
+                }
+                start = b.end.clone();
+            }
+            intervals.extend(Interval::new(start, a.end.clone()));
+        }
+        IntervalSet { intervals }
+    }
+}
+
+impl<T: Ord + Clone> From<Interval<T>> for IntervalSet<T> {
+    fn from(interval: Interval<T>) -> Self {
+        IntervalSet {
+            intervals: vec![interval],
+        }
+    }
+}
+
+impl<T: Ord + Clone> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Ord + Clone> Extend<Interval<T>> for IntervalSet<T> {
+    fn extend<I: IntoIterator<Item = Interval<T>>>(&mut self, iter: I) {
+        for interval in iter {
+            self.insert(interval);
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for IntervalSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, interval) in self.intervals.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", interval)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl<T: Ord + Clone> Contains for IntervalSet<T> {
+    type A = T;
+    type B = T;
+
+    fn contains(&self, a: &T, b: &T) -> bool {
+        self.contains_point(a) && self.contains_point(b)
+    }
+
+    fn first(&self) -> Option<&T> {
+        self.intervals.first().map(Interval::start)
+    }
+
+    fn last(&self) -> Option<&T> {
+        self.intervals.last().map(Interval::end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::property::{for_each_case, Config, Rng};
+
+    fn set(bounds: &[(u8, u8)]) -> IntervalSet<u8> {
+        bounds
+            .iter()
+            .filter_map(|&(start, end)| Interval::new(start, end))
+            .collect()
+    }
+
+    #[test]
+    fn test_interval() {
+        assert_eq!(Interval::new(3, 3), None);
+        assert_eq!(Interval::new(4, 3), None);
+        let interval = Interval::new(3, 10).unwrap();
+        assert!(interval.contains(&3, &9));
+        assert!(!interval.contains(&3, &10));
+        assert_eq!((interval.first(), interval.last()), (Some(&3), Some(&10)));
+        let touching = Interval::new(10, 12).unwrap();
+        assert!(interval.touches(&touching));
+        assert!(!interval.overlaps(&touching));
+        assert_eq!(interval.intersection(&touching), None);
+        assert_eq!(interval.to_string(), "[3, 10)");
+    }
+
+    #[test]
+    fn test_insert_merges() {
+        let mut s = set(&[(1, 3), (5, 7), (9, 11)]);
+        assert_eq!(s.to_string(), "{[1, 3), [5, 7), [9, 11)}");
+        s.insert(Interval::new(3, 5).unwrap());
+        assert_eq!(s.to_string(), "{[1, 7), [9, 11)}");
+        s.insert(Interval::new(0, 20).unwrap());
+        assert_eq!(s.intervals(), [Interval::new(0, 20).unwrap()]);
+        assert_eq!((s.first(), s.last()), (Some(&0), Some(&20)));
+        assert_eq!(IntervalSet::<u8>::new().first(), None);
+    }
+
+    // A set of values below 64, one bit per value.
+    fn bits(set: &IntervalSet<u8>) -> u64 {
+        (0..64)
+            .filter(|&v| set.contains_point(&v))
+            .fold(0, |acc, v| acc | 1 << v)
+    }
+
+    // A set built from random intervals, with its bitset model.
+    fn random_set(rng: &mut Rng) -> (IntervalSet<u8>, u64) {
+        let mut set = IntervalSet::new();
+        let mut model = 0u64;
+        for _ in 0..rng.below(6) {
+            let start = rng.below(56) as u8;
+            let end = start + 1 + rng.below(8) as u8;
+            set.insert(Interval::new(start, end).unwrap());
+            model |= (start..end).fold(0, |acc, v| acc | 1 << v);
+        }
+        (set, model)
+    }
+
+    fn assert_canonical(set: &IntervalSet<u8>) {
+        for pair in set.intervals().windows(2) {
+            assert!(pair[0].end() < pair[1].start(), "{}", set);
+        }
+    }
+
+    #[test]
+    fn test_insert_matches_bitset() {
+        for_each_case(Config::from_env(), |rng| {
+            let (set, model) = random_set(rng);
+            assert_canonical(&set);
+            assert_eq!(bits(&set), model);
+        });
+    }
+
+    #[test]
+    fn test_set_operations_match_bitset() {
+        for_each_case(Config::from_env(), |rng| {
+            let (a, a_model) = random_set(rng);
+            let (b, b_model) = random_set(rng);
+            for (result, expected) in [
+                (a.union(&b), a_model | b_model),
+                (a.intersection(&b), a_model & b_model),
+                (a.difference(&b), a_model & !b_model),
+            ] {
+                assert_canonical(&result);
+                assert_eq!(bits(&result), expected, "{} {}", a, b);
+            }
+        });
+    }
+
+    #[test]
+    fn test_contains_interval_matches_bitset() {
+        for_each_case(Config::from_env(), |rng| {
+            let (set, model) = random_set(rng);
+            let start = rng.below(60) as u8;
+            let end = start + 1 + rng.below(4) as u8;
+            let range = (start..end).fold(0u64, |acc, v| acc | 1 << v);
+            let interval = Interval::new(start, end).unwrap();
+            assert_eq!(set.contains_interval(&interval), model & range == range);
+        });
+    }
+}
@@ -0,0 +1,340 @@
+//! Extra iterator adaptors.
+//!
+//! `09_functions_HOF.rs` shows that a chain of adaptors can replace an
+//! imperative loop with a mutable accumulator. [`IteratorExt`] adds a few
+//! adaptors the standard library does not have, so that more loops can be
+//! written the same way. Every adaptor is lazy and returns `impl Iterator`,
+//! like `double_positives` in `16_traits_6_impl_trait.rs`.
+//!
+//! ```
+//! use rust_by_example::iter_ext::IteratorExt;
+//!
+//! // Running sums of odd squares, for as long as they stay under 1000.
+//! let sums: Vec<u32> = (0..)
+//!     .map(|n| n * n)
+//!     .filter(|n_squared| n_squared % 2 == 1)
+//!     .scan_while(0, |acc, n_squared| Some(acc + n_squared).filter(|&sum| sum < 1000))
+//!     .collect();
+//! assert_eq!(sums.last(), Some(&969));
+//! ```
+
+use std::{collections::VecDeque, iter};
+
+/// Adaptors available on every [`Iterator`].
+pub trait IteratorExt: Iterator + Sized {
+    /// Groups items into `Vec`s of exactly `size` items. A shorter remainder
+    /// at the end is dropped, like `slice::chunks_exact`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    ///
+    /// ```
+    /// use rust_by_example::iter_ext::IteratorExt;
+    ///
+    /// let chunks: Vec<_> = (1..=5).chunks_exact(2).collect();
+    /// assert_eq!(chunks, [vec![1, 2], vec![3, 4]]);
+    /// ```
+    fn chunks_exact(self, size: usize) -> impl Iterator<Item = Vec<Self::Item>> {
+        assert!(size != 0, "chunk size must be non-zero");
+        let mut iter = self.fuse();
+        iter::from_fn(move || {
+            let chunk: Vec<_> = iter.by_ref().take(size).collect();
+            (chunk.len() == size).then_some(chunk)
+        })
+    }
+
+    /// Yields every run of `size` consecutive items, like `slice::windows`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    ///
+    /// ```
+    /// use rust_by_example::iter_ext::IteratorExt;
+    ///
+    /// let windows: Vec<_> = "abcd".chars().windows(3).collect();
+    /// assert_eq!(windows, [vec!['a', 'b', 'c'], vec!['b', 'c', 'd']]);
+    /// ```
+    fn windows(self, size: usize) -> impl Iterator<Item = Vec<Self::Item>>
+    where
+        Self::Item: Clone,
+    {
+        assert!(size != 0, "window size must be non-zero");
+        let mut iter = self.fuse();
+        let mut window = VecDeque::with_capacity(size);
+        iter::from_fn(move || {
+            if window.len() == size {
+                window.pop_front();
+            }
+            while window.len() < size {
+                window.push_back(iter.next()?);
+            }
+            Some(window.iter().cloned().collect())
+        })
+    }
+
+    /// Alternates between items of `self` and `other`, starting with `self`.
+    /// Once either side runs out, the rest of the other one follows.
+    ///
+    /// ```
+    /// use rust_by_example::iter_ext::IteratorExt;
+    ///
+    /// let mixed: Vec<_> = [1, 2, 3].into_iter().interleave([10, 20]).collect();
+    /// assert_eq!(mixed, [1, 10, 2, 20, 3]);
+    /// ```
+    fn interleave<I>(self, other: I) -> impl Iterator<Item = Self::Item>
+    where
+        I: IntoIterator<Item = Self::Item>,
+    {
+        let mut first = self.fuse();
+        let mut second = other.into_iter().fuse();
+        let mut from_first = false;
+        iter::from_fn(move || {
+            from_first = !from_first;
+            if from_first {
+                first.next().or_else(|| second.next())
+            } else {
+                second.next().or_else(|| first.next())
+            }
+        })
+    }
+
+    /// Drops items whose key equals the key of the item kept just before
+    /// them, like `Vec::dedup_by_key`.
+    ///
+    /// ```
+    /// use rust_by_example::iter_ext::IteratorExt;
+    ///
+    /// let words: Vec<_> = ["apple", "avocado", "banana", "apricot"]
+    ///     .into_iter()
+    ///     .dedup_by_key(|word| word.chars().next())
+    ///     .collect();
+    /// assert_eq!(words, ["apple", "banana", "apricot"]);
+    /// ```
+    fn dedup_by_key<K, F>(self, mut key: F) -> impl Iterator<Item = Self::Item>
+    where
+        K: PartialEq,
+        F: FnMut(&Self::Item) -> K,
+    {
+        let mut last = None;
+        self.filter(move |item| {
+            let current = key(item);
+            if last.as_ref() == Some(&current) {
+                false
+            } else {
+                last = Some(current);
+                true
+            }
+        })
+    }
+
+    /// Splits the items into runs of consecutive items with equal keys, and
+    /// yields each run together with its key.
+    ///
+    /// ```
+    /// use rust_by_example::iter_ext::IteratorExt;
+    ///
+    /// let runs: Vec<_> = [1, 3, 2, 4, 6, 5].into_iter().group_by(|n| n % 2).collect();
+    /// assert_eq!(runs, [(1, vec![1, 3]), (0, vec![2, 4, 6]), (1, vec![5])]);
+    /// ```
+    fn group_by<K, F>(self, mut key: F) -> impl Iterator<Item = (K, Vec<Self::Item>)>
+    where
+        K: PartialEq,
+        F: FnMut(&Self::Item) -> K,
+    {
+        let mut iter = self.peekable();
+        iter::from_fn(move || {
+            let first = iter.next()?;
+            let group_key = key(&first);
+            let mut group = vec![first];
+            while let Some(item) = iter.next_if(|item| key(item) == group_key) {
+                group.push(item);
+            }
+            Some((group_key, group))
+        })
+    }
+
+    /// Yields items up to and including the first one that satisfies
+    /// `predicate`. Unlike `take_while`, the deciding item is not lost.
+    ///
+    /// ```
+    /// use rust_by_example::iter_ext::IteratorExt;
+    ///
+    /// let squares: Vec<u32> = (0..).map(|n| n * n).take_until_inclusive(|&sq| sq >= 10).collect();
+    /// assert_eq!(squares, [0, 1, 4, 9, 16]);
+    /// ```
+    fn take_until_inclusive<P>(mut self, mut predicate: P) -> impl Iterator<Item = Self::Item>
+    where
+        P: FnMut(&Self::Item) -> bool,
+    {
+        let mut done = false;
+        iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let item = self.next()?;
+            done = predicate(&item);
+            Some(item)
+        })
+    }
+
+    /// Folds the items into a state and yields every intermediate state, for
+    /// as long as `step` returns `Some`.
+    ///
+    /// ```
+    /// use rust_by_example::iter_ext::IteratorExt;
+    ///
+    /// let totals: Vec<_> = [5, 3, 4, 1].into_iter()
+    ///     .scan_while(0, |total, n| (total + n <= 10).then_some(total + n))
+    ///     .collect();
+    /// assert_eq!(totals, [5, 8]);
+    /// ```
+    fn scan_while<St, F>(mut self, init: St, mut step: F) -> impl Iterator<Item = St>
+    where
+        St: Clone,
+        F: FnMut(&St, Self::Item) -> Option<St>,
+    {
+        let mut state = Some(init);
+        iter::from_fn(move || {
+            let current = state.take()?;
+            let next = step(&current, self.next()?)?;
+            state = Some(next.clone());
+            Some(next)
+        })
+    }
+}
+
+impl<I: Iterator> IteratorExt for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::property::{for_each_case, Config, Rng};
+
+    fn random_vec(rng: &mut Rng) -> Vec<u8> {
+        let len = rng.below(20);
+        // Few distinct values, so that runs and duplicates are common.
+        (0..len).map(|_| rng.below(4) as u8).collect()
+    }
+
+    #[test]
+    fn test_chunks_exact_matches_slice() {
+        for_each_case(Config::from_env(), |rng| {
+            let v = random_vec(rng);
+            let size = rng.below(5) as usize + 1;
+            let expected: Vec<Vec<u8>> = v.chunks_exact(size).map(<[u8]>::to_vec).collect();
+            assert_eq!(
+                v.iter().copied().chunks_exact(size).collect::<Vec<_>>(),
+                expected
+            );
+        });
+    }
+
+    #[test]
+    fn test_windows_matches_slice() {
+        for_each_case(Config::from_env(), |rng| {
+            let v = random_vec(rng);
+            let size = rng.below(5) as usize + 1;
+            let expected: Vec<Vec<u8>> = v.windows(size).map(<[u8]>::to_vec).collect();
+            assert_eq!(
+                IteratorExt::windows(v.iter().copied(), size).collect::<Vec<_>>(),
+                expected
+            );
+        });
+    }
+
+    #[test]
+    fn test_interleave_matches_naive() {
+        for_each_case(Config::from_env(), |rng| {
+            let (a, b) = (random_vec(rng), random_vec(rng));
+            let mut expected = Vec::new();
+            for i in 0..a.len().max(b.len()) {
+                expected.extend(a.get(i));
+                expected.extend(b.get(i));
+            }
+            let actual: Vec<u8> = a.iter().interleave(&b).copied().collect();
+            assert_eq!(actual, expected);
+        });
+    }
+
+    #[test]
+    fn test_dedup_by_key_matches_vec() {
+        for_each_case(Config::from_env(), |rng| {
+            let v = random_vec(rng);
+            let mut expected = v.clone();
+            expected.dedup_by_key(|n| *n / 2);
+            let actual: Vec<u8> = IteratorExt::dedup_by_key(v.into_iter(), |n| *n / 2).collect();
+            assert_eq!(actual, expected);
+        });
+    }
+
+    #[test]
+    fn test_group_by_matches_naive() {
+        for_each_case(Config::from_env(), |rng| {
+            let v = random_vec(rng);
+            let mut expected: Vec<(bool, Vec<u8>)> = Vec::new();
+            for &n in &v {
+                match expected.last_mut() {
+                    Some((key, group)) if *key == (n % 2 == 0) => group.push(n),
+                    _ => expected.push((n % 2 == 0, vec![n])),
+                }
+            }
+            let actual: Vec<_> = v.into_iter().group_by(|n| n % 2 == 0).collect();
+            assert_eq!(actual, expected);
+        });
+    }
+
+    #[test]
+    fn test_take_until_inclusive_matches_naive() {
+        for_each_case(Config::from_env(), |rng| {
+            let v = random_vec(rng);
+            let expected = match v.iter().position(|&n| n == 3) {
+                Some(i) => &v[..=i],
+                None => &v[..],
+            };
+            let actual: Vec<u8> = v
+                .iter()
+                .copied()
+                .take_until_inclusive(|&n| n == 3)
+                .collect();
+            assert_eq!(actual, expected);
+        });
+    }
+
+    #[test]
+    fn test_scan_while_matches_naive() {
+        for_each_case(Config::from_env(), |rng| {
+            let v = random_vec(rng);
+            let limit = rng.below(30) as u32;
+            let mut expected = Vec::new();
+            let mut total = 0;
+            for &n in &v {
+                total += n as u32;
+                if total > limit {
+                    break;
+                }
+                expected.push(total);
+            }
+            let actual: Vec<u32> = v
+                .iter()
+                .scan_while(0, |total, &n| {
+                    Some(total + n as u32).filter(|&t| t <= limit)
+                })
+                .collect();
+            assert_eq!(actual, expected);
+        });
+    }
+
+    #[test]
+    fn test_adaptors_are_lazy() {
+        let firsts: Vec<_> = (0..)
+            .chunks_exact(3)
+            .map(|chunk| chunk[0])
+            .take(3)
+            .collect();
+        assert_eq!(firsts, [0, 3, 6]);
+        let runs = (0..).group_by(|n| n / 10).nth(2);
+        assert_eq!(runs, Some((2, (20..30).collect())));
+    }
+}
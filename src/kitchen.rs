@@ -0,0 +1,9 @@
+//! Cooking, without the `Option`s.
+//!
+//! The combinator examples in chapter 18 model food preparation with
+//! `Option`, which can say that something went wrong but not what. The
+//! modules here keep the same story and report failures as `Result`s.
+
+pub mod pipeline;
+pub mod planner;
+pub mod shopping;
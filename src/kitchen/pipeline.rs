@@ -0,0 +1,329 @@
+//! A typestate food-processing pipeline.
+//!
+//! `18_error_handling_3_2_combinators_map.rs` threads an `Option<Food>`
+//! through `peel`, `chop` and `cook`. Here a [`Dish`] carries its stage in a
+//! phantom type parameter instead, so each step is only callable in the
+//! right stage, and each step returns a [`StageError`] explaining what went
+//! wrong instead of a bare `None`.
+//!
+//! ```
+//! use rust_by_example::kitchen::pipeline::{Dish, Food, Produce};
+//!
+//! let cooked = Dish::new(Produce::fresh(Food::Carrot, 120))
+//!     .peel()?
+//!     .chop()?
+//!     .cook()?;
+//! assert_eq!(cooked.food(), Food::Carrot);
+//! # Ok::<(), rust_by_example::kitchen::pipeline::StageError>(())
+//! ```
+//!
+//! Skipping a stage does not compile:
+//!
+//! ```compile_fail
+//! use rust_by_example::kitchen::pipeline::{Dish, Food, Produce};
+//!
+//! let raw = Dish::new(Produce::fresh(Food::Potato, 200));
+//! raw.cook();
+//! ```
+
+use std::{error, fmt, marker::PhantomData};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Food {
+    Apple,
+    Carrot,
+    Potato,
+}
+
+/// A piece of food as it comes out of the pantry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Produce {
+    pub food: Food,
+    pub grams: u32,
+    pub rotten: bool,
+    pub frozen: bool,
+}
+
+impl Produce {
+    /// Produce that is neither rotten nor frozen.
+    pub fn fresh(food: Food, grams: u32) -> Produce {
+        Produce {
+            food,
+            grams,
+            rotten: false,
+            frozen: false,
+        }
+    }
+}
+
+/// Stage marker: nothing has been done yet.
+#[derive(Debug)]
+pub enum Raw {}
+/// Stage marker: the skin is off.
+#[derive(Debug)]
+pub enum Peeled {}
+/// Stage marker: cut into pieces.
+#[derive(Debug)]
+pub enum Chopped {}
+/// Stage marker: ready to eat.
+#[derive(Debug)]
+pub enum Cooked {}
+
+/// The steps of the pipeline, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Peel,
+    Chop,
+    Cook,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 3] = [Stage::Peel, Stage::Chop, Stage::Cook];
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Peel => write!(f, "peel"),
+            Stage::Chop => write!(f, "chop"),
+            Stage::Cook => write!(f, "cook"),
+        }
+    }
+}
+
+/// Why a stage could not be completed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reason {
+    Rotten,
+    Frozen,
+    /// Less food was left than the stage needs.
+    TooLittle {
+        grams: u32,
+        needed: u32,
+    },
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::Rotten => write!(f, "it is rotten"),
+            Reason::Frozen => write!(f, "it is frozen"),
+            Reason::TooLittle { grams, needed } => {
+                write!(f, "only {}g left, {}g needed", grams, needed)
+            }
+        }
+    }
+}
+
+/// A failed step, with the stage it failed at and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageError {
+    pub stage: Stage,
+    pub food: Food,
+    pub reason: Reason,
+}
+
+impl fmt::Display for StageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "could not {} {:?}: {}",
+            self.stage, self.food, self.reason
+        )
+    }
+}
+
+impl error::Error for StageError {}
+
+/// Peeling takes off this share of the weight, in percent.
+pub const PEEL_LOSS_PERCENT: u32 = 10;
+/// Anything lighter than this burns in the pan.
+pub const MIN_COOK_GRAMS: u32 = 50;
+
+/// Food on its way through the pipeline, in stage `S`.
+#[derive(Debug)]
+pub struct Dish<S> {
+    produce: Produce,
+    stage: PhantomData<S>,
+}
+
+impl<S> Dish<S> {
+    pub fn food(&self) -> Food {
+        self.produce.food
+    }
+
+    pub fn grams(&self) -> u32 {
+        self.produce.grams
+    }
+
+    fn advance<T>(self) -> Dish<T> {
+        Dish {
+            produce: self.produce,
+            stage: PhantomData,
+        }
+    }
+
+    fn fail(&self, stage: Stage, reason: Reason) -> StageError {
+        StageError {
+            stage,
+            food: self.produce.food,
+            reason,
+        }
+    }
+}
+
+impl Dish<Raw> {
+    pub fn new(produce: Produce) -> Dish<Raw> {
+        Dish {
+            produce,
+            stage: PhantomData,
+        }
+    }
+
+    /// Peels the food, losing [`PEEL_LOSS_PERCENT`] of its weight.
+    /// Rotten food cannot be peeled.
+    pub fn peel(mut self) -> Result<Dish<Peeled>, StageError> {
+        if self.produce.rotten {
+            return Err(self.fail(Stage::Peel, Reason::Rotten));
+        }
+        // Widened, so that the product cannot overflow. The loss is at most
+        // the weight, so it fits back into a `u32`.
+        let loss = u64::from(self.produce.grams) * u64::from(PEEL_LOSS_PERCENT) / 100;
+        self.produce.grams -= loss as u32;
+        Ok(self.advance())
+    }
+}
+
+impl Dish<Peeled> {
+    /// Chops the food. Frozen food cannot be chopped.
+    pub fn chop(self) -> Result<Dish<Chopped>, StageError> {
+        if self.produce.frozen {
+            return Err(self.fail(Stage::Chop, Reason::Frozen));
+        }
+        Ok(self.advance())
+    }
+}
+
+impl Dish<Chopped> {
+    /// Cooks the food, which needs at least [`MIN_COOK_GRAMS`].
+    pub fn cook(self) -> Result<Dish<Cooked>, StageError> {
+        if self.produce.grams < MIN_COOK_GRAMS {
+            let reason = Reason::TooLittle {
+                grams: self.produce.grams,
+                needed: MIN_COOK_GRAMS,
+            };
+            return Err(self.fail(Stage::Cook, reason));
+        }
+        Ok(self.advance())
+    }
+}
+
+/// Runs `produce` through every stage, stopping at the first failure.
+pub fn process(produce: Produce) -> Result<Dish<Cooked>, StageError> {
+    Dish::new(produce).peel()?.chop()?.cook()
+}
+
+/// The outcome of processing a list of foods.
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    pub cooked: Vec<Dish<Cooked>>,
+    pub failures: Vec<StageError>,
+}
+
+impl BatchReport {
+    /// The number of foods that failed at `stage`.
+    pub fn failed_at(&self, stage: Stage) -> usize {
+        self.failures.iter().filter(|e| e.stage == stage).count()
+    }
+}
+
+impl fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "cooked: {}", self.cooked.len())?;
+        for stage in Stage::ALL {
+            writeln!(f, "failed to {}: {}", stage, self.failed_at(stage))?;
+        }
+        for failure in &self.failures {
+            writeln!(f, "  {}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+/// Processes every food in `batch`, collecting successes and failures.
+pub fn process_batch<I: IntoIterator<Item = Produce>>(batch: I) -> BatchReport {
+    let mut report = BatchReport::default();
+    for produce in batch {
+        match process(produce) {
+            Ok(dish) => report.cooked.push(dish),
+            Err(e) => report.failures.push(e),
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_stage_fails_with_its_reason() {
+        let rotten = Produce {
+            rotten: true,
+            ..Produce::fresh(Food::Apple, 100)
+        };
+        let error = process(rotten).unwrap_err();
+        assert_eq!((error.stage, error.reason), (Stage::Peel, Reason::Rotten));
+
+        let frozen = Produce {
+            frozen: true,
+            ..Produce::fresh(Food::Carrot, 100)
+        };
+        let error = process(frozen).unwrap_err();
+        assert_eq!((error.stage, error.reason), (Stage::Chop, Reason::Frozen));
+
+        let error = process(Produce::fresh(Food::Potato, 50)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "could not cook Potato: only 45g left, 50g needed"
+        );
+    }
+
+    #[test]
+    fn test_peeling_loses_weight() {
+        let peeled = Dish::new(Produce::fresh(Food::Potato, 200)).peel().unwrap();
+        assert_eq!(peeled.grams(), 180);
+        let cooked = process(Produce::fresh(Food::Potato, 56)).unwrap();
+        assert_eq!(cooked.grams(), 51);
+        let heaviest = Dish::new(Produce::fresh(Food::Potato, u32::MAX))
+            .peel()
+            .unwrap();
+        assert_eq!(heaviest.grams(), u32::MAX - u32::MAX / 10);
+    }
+
+    #[test]
+    fn test_batch_report() {
+        let report = process_batch([
+            Produce::fresh(Food::Apple, 150),
+            Produce {
+                rotten: true,
+                ..Produce::fresh(Food::Apple, 150)
+            },
+            Produce::fresh(Food::Carrot, 30),
+            Produce::fresh(Food::Potato, 300),
+        ]);
+        assert_eq!(report.cooked.len(), 2);
+        assert_eq!(report.failed_at(Stage::Peel), 1);
+        assert_eq!(report.failed_at(Stage::Chop), 0);
+        assert_eq!(report.failed_at(Stage::Cook), 1);
+        assert_eq!(
+            report.to_string(),
+            "cooked: 2\n\
+             failed to peel: 1\n\
+             failed to chop: 0\n\
+             failed to cook: 1\n  \
+             could not peel Apple: it is rotten\n  \
+             could not cook Carrot: only 27g left, 50g needed\n"
+        );
+    }
+}
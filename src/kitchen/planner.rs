@@ -0,0 +1,478 @@
+//! An ingredient inventory and a weekly meal planner.
+//!
+//! `18_error_handling_3_3_combinators_and_then.rs` hard-codes that there are
+//! no ingredients for sushi and no recipe for cordon bleu. Here both facts
+//! come from data: an [`Inventory`] tracks how much of each ingredient is
+//! left, a [`Cookbook`] lists what every dish consumes, and
+//! [`Kitchen::cookable`] explains exactly what is missing.
+//!
+//! ```
+//! use rust_by_example::kitchen::planner::{CookError, Cookbook, Inventory, Kitchen};
+//!
+//! let inventory = Inventory::parse("beef 1\nrice 500\n")?;
+//! let cookbook = Cookbook::parse("steak: beef 1\nsushi: rice 200, nori 2\n")?;
+//! let kitchen = Kitchen::new(inventory, cookbook);
+//!
+//! assert!(kitchen.cookable("steak").is_ok());
+//! assert!(matches!(kitchen.cookable("cordon bleu"), Err(CookError::NoRecipe(_))));
+//! let err = kitchen.cookable("sushi").unwrap_err();
+//! assert_eq!(err.to_string(), "cannot make sushi: missing nori (need 2, have 0)");
+//! # Ok::<(), rust_by_example::kitchen::planner::LoadError>(())
+//! ```
+
+use std::{collections::BTreeMap, error, fmt, fs, io, path::Path};
+
+/// The errors that can occur while reading inventory or recipe files.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Syntax { line: usize, message: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "could not read file: {}", e),
+            LoadError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            LoadError::Io(e) => Some(e),
+            LoadError::Syntax { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+// Yields the numbered, trimmed lines of `text` that are not blank or comments.
+pub(super) fn content_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+// Parses `<ingredient> <quantity>`, where the ingredient may contain spaces.
+fn parse_amount(text: &str, line: usize) -> Result<(String, u32), LoadError> {
+    let syntax = |message| LoadError::Syntax { line, message };
+    let (name, quantity) = text.trim().rsplit_once(' ').ok_or_else(|| {
+        syntax(format!(
+            "expected `<ingredient> <quantity>`, got `{}`",
+            text.trim()
+        ))
+    })?;
+    let quantity = quantity
+        .parse()
+        .map_err(|_| syntax(format!("invalid quantity `{}`", quantity)))?;
+    Ok((name.trim().to_string(), quantity))
+}
+
+// Adds `quantity` to what `totals` has of `name` so far, for line `line`.
+fn add_up(
+    totals: &mut BTreeMap<String, u32>,
+    name: String,
+    quantity: u32,
+    line: usize,
+) -> Result<(), LoadError> {
+    let total = totals.entry(name).or_insert(0);
+    match total.checked_add(quantity) {
+        Some(sum) => {
+            *total = sum;
+            Ok(())
+        }
+        None => Err(LoadError::Syntax {
+            line,
+            message: format!("the total exceeds {}", u32::MAX),
+        }),
+    }
+}
+
+/// How much of an ingredient a dish needs, and how much there is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shortage {
+    pub ingredient: String,
+    pub needed: u32,
+    pub available: u32,
+}
+
+impl fmt::Display for Shortage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (need {}, have {})",
+            self.ingredient, self.needed, self.available
+        )
+    }
+}
+
+/// Ingredient quantities, in whatever unit each ingredient is counted in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Inventory {
+    stock: BTreeMap<String, u32>,
+}
+
+impl Inventory {
+    pub fn new() -> Inventory {
+        Inventory::default()
+    }
+
+    /// Parses one `<ingredient> <quantity>` per line. Repeated ingredients
+    /// are added up.
+    pub fn parse(text: &str) -> Result<Inventory, LoadError> {
+        let mut inventory = Inventory::new();
+        for (line, content) in content_lines(text) {
+            let (name, quantity) = parse_amount(content, line)?;
+            add_up(&mut inventory.stock, name, quantity, line)?;
+        }
+        Ok(inventory)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Inventory, LoadError> {
+        Inventory::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Adds to the stock of `ingredient`, which stops at `u32::MAX`.
+    pub fn add(&mut self, ingredient: &str, quantity: u32) {
+        let stock = self.stock.entry(ingredient.to_string()).or_insert(0);
+        *stock = stock.saturating_add(quantity);
+    }
+
+    pub fn quantity(&self, ingredient: &str) -> u32 {
+        self.stock.get(ingredient).copied().unwrap_or(0)
+    }
+
+    /// Every ingredient `recipe` needs more of than is in stock.
+    pub fn shortages(&self, recipe: &Recipe) -> Vec<Shortage> {
+        recipe
+            .ingredients
+            .iter()
+            .filter_map(|(ingredient, &needed)| {
+                let available = self.quantity(ingredient);
+                (available < needed).then(|| Shortage {
+                    ingredient: ingredient.clone(),
+                    needed,
+                    available,
+                })
+            })
+            .collect()
+    }
+
+    /// Takes the ingredients of `recipe` out of stock, or changes nothing
+    /// and returns the shortages.
+    pub fn consume(&mut self, recipe: &Recipe) -> Result<(), Vec<Shortage>> {
+        let shortages = self.shortages(recipe);
+        if !shortages.is_empty() {
+            return Err(shortages);
+        }
+        for (ingredient, needed) in &recipe.ingredients {
+            if let Some(stock) = self.stock.get_mut(ingredient) {
+                *stock -= needed;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A dish and the ingredients it consumes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipe {
+    pub dish: String,
+    pub ingredients: BTreeMap<String, u32>,
+}
+
+/// Every known recipe, by dish name.
+#[derive(Debug, Clone, Default)]
+pub struct Cookbook {
+    recipes: BTreeMap<String, Recipe>,
+}
+
+impl Cookbook {
+    /// Parses one `<dish>: <ingredient> <quantity>, ...` per line. Each dish
+    /// may have only one line.
+    pub fn parse(text: &str) -> Result<Cookbook, LoadError> {
+        let mut cookbook = Cookbook::default();
+        for (line, content) in content_lines(text) {
+            let (dish, amounts) = content.split_once(':').ok_or_else(|| LoadError::Syntax {
+                line,
+                message: format!("expected `<dish>: <ingredients>`, got `{}`", content),
+            })?;
+            let mut ingredients = BTreeMap::new();
+            for amount in amounts.split(',') {
+                let (name, quantity) = parse_amount(amount, line)?;
+                add_up(&mut ingredients, name, quantity, line)?;
+            }
+            let dish = dish.trim().to_string();
+            if cookbook.recipes.contains_key(&dish) {
+                return Err(LoadError::Syntax {
+                    line,
+                    message: format!("a second recipe for `{}`", dish),
+                });
+            }
+            cookbook
+                .recipes
+                .insert(dish.clone(), Recipe { dish, ingredients });
+        }
+        Ok(cookbook)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Cookbook, LoadError> {
+        Cookbook::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn recipe(&self, dish: &str) -> Option<&Recipe> {
+        self.recipes.get(dish)
+    }
+}
+
+/// Why a dish cannot be made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CookError {
+    NoRecipe(String),
+    MissingIngredients {
+        dish: String,
+        missing: Vec<Shortage>,
+    },
+}
+
+impl fmt::Display for CookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CookError::NoRecipe(dish) => write!(f, "there is no recipe for {}", dish),
+            CookError::MissingIngredients { dish, missing } => {
+                write!(f, "cannot make {}: missing ", dish)?;
+                for (i, shortage) in missing.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", shortage)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl error::Error for CookError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Day {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Day {
+    pub const WEEK: [Day; 7] = [
+        Day::Monday,
+        Day::Tuesday,
+        Day::Wednesday,
+        Day::Thursday,
+        Day::Friday,
+        Day::Saturday,
+        Day::Sunday,
+    ];
+}
+
+/// The dish picked for each day, if any, and what is left afterwards.
+#[derive(Debug, Clone)]
+pub struct WeeklyPlan {
+    pub meals: Vec<(Day, Option<String>)>,
+    pub leftovers: Inventory,
+}
+
+impl fmt::Display for WeeklyPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (day, meal) in &self.meals {
+            match meal {
+                Some(dish) => writeln!(f, "Yay! On {:?} we get to eat {}.", day, dish)?,
+                None => writeln!(f, "Oh no. We don't get to eat on {:?}?", day)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An inventory together with the recipes that draw on it.
+#[derive(Debug, Clone)]
+pub struct Kitchen {
+    pub inventory: Inventory,
+    pub cookbook: Cookbook,
+}
+
+impl Kitchen {
+    pub fn new(inventory: Inventory, cookbook: Cookbook) -> Kitchen {
+        Kitchen {
+            inventory,
+            cookbook,
+        }
+    }
+
+    /// Loads `pantry.txt` and `recipes.txt` from `dir`, e.g. `data/`.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Kitchen, LoadError> {
+        let dir = dir.as_ref();
+        Ok(Kitchen::new(
+            Inventory::load(dir.join("pantry.txt"))?,
+            Cookbook::load(dir.join("recipes.txt"))?,
+        ))
+    }
+
+    /// Returns the recipe for `dish` if there is one and everything it needs
+    /// is in stock.
+    pub fn cookable(&self, dish: &str) -> Result<&Recipe, CookError> {
+        let recipe = self
+            .cookbook
+            .recipe(dish)
+            .ok_or_else(|| CookError::NoRecipe(dish.to_string()))?;
+        let missing = self.inventory.shortages(recipe);
+        if missing.is_empty() {
+            Ok(recipe)
+        } else {
+            Err(CookError::MissingIngredients {
+                dish: dish.to_string(),
+                missing,
+            })
+        }
+    }
+
+    /// Cooks `dish`, taking its ingredients out of the inventory.
+    pub fn cook(&mut self, dish: &str) -> Result<(), CookError> {
+        let recipe = self.cookable(dish)?.clone();
+        self.inventory
+            .consume(&recipe)
+            .expect("cookable recipes have no shortages");
+        Ok(())
+    }
+
+    /// Plans a meal for every day of the week without cooking anything.
+    ///
+    /// Each day gets the first dish in `wishes` that can still be made from
+    /// what the earlier days left over; a day stays empty if none can.
+    pub fn plan_week(&self, wishes: &[&str]) -> WeeklyPlan {
+        let mut kitchen = self.clone();
+        let meals = Day::WEEK
+            .iter()
+            .map(|&day| {
+                let dish = wishes.iter().find(|dish| kitchen.cook(dish).is_ok());
+                (day, dish.map(|dish| dish.to_string()))
+            })
+            .collect();
+        WeeklyPlan {
+            meals,
+            leftovers: kitchen.inventory,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kitchen() -> Kitchen {
+        Kitchen::load("data").unwrap()
+    }
+
+    #[test]
+    fn test_example_facts_come_from_data() {
+        let kitchen = kitchen();
+        assert!(kitchen.cookable("steak").is_ok());
+        assert_eq!(
+            kitchen.cookable("cordon bleu"),
+            Err(CookError::NoRecipe("cordon bleu".to_string()))
+        );
+        let Err(CookError::MissingIngredients { missing, .. }) = kitchen.cookable("sushi") else {
+            panic!("sushi should be missing ingredients");
+        };
+        assert_eq!(
+            missing,
+            [Shortage {
+                ingredient: "nori".to_string(),
+                needed: 2,
+                available: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cook_consumes_ingredients() {
+        let mut kitchen = kitchen();
+        for _ in 0..3 {
+            kitchen.cook("steak").unwrap();
+        }
+        assert_eq!(kitchen.inventory.quantity("beef"), 0);
+        assert_eq!(kitchen.inventory.quantity("butter"), 1);
+        let err = kitchen.cook("steak").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot make steak: missing beef (need 1, have 0)"
+        );
+        assert_eq!(kitchen.inventory.quantity("salt"), 7);
+    }
+
+    #[test]
+    fn test_plan_week_never_overdraws() {
+        let kitchen = kitchen();
+        let plan = kitchen.plan_week(&["cordon bleu", "sushi", "steak", "rice bowl"]);
+        let dishes: Vec<_> = plan.meals.iter().map(|(_, dish)| dish.as_deref()).collect();
+        assert_eq!(
+            dishes,
+            [
+                Some("steak"),
+                Some("steak"),
+                Some("steak"),
+                Some("rice bowl"),
+                Some("rice bowl"),
+                None,
+                None
+            ]
+        );
+        assert_eq!(plan.leftovers.quantity("rice"), 300);
+        assert_eq!(plan.leftovers.quantity("salt"), 5);
+        // Planning does not touch the real inventory.
+        assert_eq!(kitchen.inventory.quantity("beef"), 3);
+        assert!(plan
+            .to_string()
+            .ends_with("Oh no. We don't get to eat on Sunday?\n"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Inventory::parse("rice 10\nbeans\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: expected `<ingredient> <quantity>`, got `beans`"
+        );
+        let err = Cookbook::parse("# soup\nsoup water lots\n").unwrap_err();
+        assert!(matches!(err, LoadError::Syntax { line: 2, .. }));
+        let err = Cookbook::parse("soup: water lots").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: invalid quantity `lots`");
+        let err = Cookbook::parse("soup: water 1\n\nsoup: stone 1\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 3: a second recipe for `soup`");
+    }
+
+    #[test]
+    fn test_totals_cannot_overflow() {
+        let max = u32::MAX;
+        let err = Inventory::parse(&format!("rice {}\nrice 1\n", max)).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: the total exceeds 4294967295");
+        let err = Cookbook::parse(&format!("soup: water {}, water 1", max)).unwrap_err();
+        assert!(matches!(err, LoadError::Syntax { line: 1, .. }));
+
+        let mut inventory = Inventory::new();
+        inventory.add("rice", max);
+        inventory.add("rice", 1);
+        assert_eq!(inventory.quantity("rice"), max);
+    }
+}
@@ -0,0 +1,561 @@
+//! "Count item" lines and shopping lists.
+//!
+//! `get_count_item` in `08_flow_of_control_match_let-else.rs` splits
+//! `"3 chairs"` on a single space and panics on anything else. [`CountItem`]
+//! parses the same lines, plus measured ones like `2.5 kg flour`, and
+//! reports what is wrong instead. Item names are normalized to the
+//! singular, so that a [`ShoppingList`] can add `1 onion` and `2 onions`
+//! together.
+//!
+//! ```
+//! use rust_by_example::kitchen::shopping::{CountItem, ShoppingList};
+//!
+//! let item: CountItem = "3 chairs".parse()?;
+//! assert_eq!((item.item.as_str(), item.to_string().as_str()), ("chair", "3 chairs"));
+//!
+//! let list = ShoppingList::parse("1 onion\n500 g flour\n2 Onions\n2.5 kg flour\n").unwrap();
+//! assert_eq!(list.to_string(), "3 kg flour\n3 onions\n");
+//! # Ok::<(), rust_by_example::kitchen::shopping::ItemError>(())
+//! ```
+
+use super::planner::{content_lines, LoadError};
+use std::{collections::BTreeMap, error, fmt, fs, path::Path, str::FromStr};
+
+/// What a quantity measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Measure {
+    Count,
+    /// In grams.
+    Mass,
+    /// In millilitres.
+    Volume,
+}
+
+impl fmt::Display for Measure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Measure::Count => write!(f, "count"),
+            Measure::Mass => write!(f, "mass"),
+            Measure::Volume => write!(f, "volume"),
+        }
+    }
+}
+
+/// A unit a line may give after its number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Gram,
+    Kilogram,
+    Millilitre,
+    Litre,
+}
+
+impl Unit {
+    /// Recognizes the usual spellings, abbreviations and plurals.
+    pub fn from_word(word: &str) -> Option<Unit> {
+        match word.to_lowercase().as_str() {
+            "g" | "gram" | "grams" => Some(Unit::Gram),
+            "kg" | "kilo" | "kilos" | "kilogram" | "kilograms" => Some(Unit::Kilogram),
+            "ml" | "millilitre" | "millilitres" | "milliliter" | "milliliters" => {
+                Some(Unit::Millilitre)
+            }
+            "l" | "litre" | "litres" | "liter" | "liters" => Some(Unit::Litre),
+            _ => None,
+        }
+    }
+
+    pub fn measure(self) -> Measure {
+        match self {
+            Unit::Gram | Unit::Kilogram => Measure::Mass,
+            Unit::Millilitre | Unit::Litre => Measure::Volume,
+        }
+    }
+
+    /// How many grams or millilitres one of this unit is.
+    fn scale(self) -> u64 {
+        match self {
+            Unit::Gram | Unit::Millilitre => 1,
+            Unit::Kilogram | Unit::Litre => 1000,
+        }
+    }
+}
+
+/// An exact amount, kept in thousandths of an item, gram or millilitre.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quantity {
+    pub measure: Measure,
+    thousandths: u64,
+}
+
+impl Quantity {
+    /// The amount in items, grams or millilitres.
+    pub fn amount(&self) -> f64 {
+        self.thousandths as f64 / 1000.0
+    }
+
+    fn checked_add(self, other: Quantity) -> Option<Quantity> {
+        if self.measure != other.measure {
+            return None;
+        }
+        Some(Quantity {
+            measure: self.measure,
+            thousandths: self.thousandths.checked_add(other.thousandths)?,
+        })
+    }
+}
+
+// Writes `thousandths / 1000` without trailing zeros.
+fn write_decimal(f: &mut fmt::Formatter<'_>, thousandths: u64) -> fmt::Result {
+    let (whole, fraction) = (thousandths / 1000, thousandths % 1000);
+    if fraction == 0 {
+        write!(f, "{}", whole)
+    } else {
+        let digits = format!("{:03}", fraction);
+        write!(f, "{}.{}", whole, digits.trim_end_matches('0'))
+    }
+}
+
+impl fmt::Display for Quantity {
+    /// Counts print bare, masses and volumes in the largest unit that keeps
+    /// the number at least 1 and needs no more than three decimals, so that
+    /// the output parses back.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (small, large) = match self.measure {
+            Measure::Count => return write_decimal(f, self.thousandths),
+            Measure::Mass => ("g", "kg"),
+            Measure::Volume => ("ml", "l"),
+        };
+        if self.thousandths >= 1_000_000 && self.thousandths.is_multiple_of(1000) {
+            write_decimal(f, self.thousandths / 1000)?;
+            write!(f, " {}", large)
+        } else {
+            write_decimal(f, self.thousandths)?;
+            write!(f, " {}", small)
+        }
+    }
+}
+
+/// Why a single line could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemError {
+    Empty,
+    MissingItem(String),
+    InvalidNumber(String),
+    /// More than three decimal places.
+    TooPrecise(String),
+    FractionalCount(String),
+    TooLarge(String),
+}
+
+impl fmt::Display for ItemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemError::Empty => write!(f, "expected `<count> [<unit>] <item>`, got nothing"),
+            ItemError::MissingItem(text) => write!(f, "no item after `{}`", text),
+            ItemError::InvalidNumber(number) => write!(f, "invalid number `{}`", number),
+            ItemError::TooPrecise(number) => {
+                write!(f, "`{}` has more than three decimal places", number)
+            }
+            ItemError::FractionalCount(number) => {
+                write!(f, "cannot count `{}` items, only whole ones", number)
+            }
+            ItemError::TooLarge(number) => write!(f, "`{}` is too large", number),
+        }
+    }
+}
+
+impl error::Error for ItemError {}
+
+// Parses a non-negative decimal with up to three places into thousandths.
+fn parse_thousandths(number: &str) -> Result<u64, ItemError> {
+    let invalid = || ItemError::InvalidNumber(number.to_string());
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() || !all_digits(whole) || !all_digits(fraction) {
+        return Err(invalid());
+    }
+    if fraction.len() > 3 {
+        return Err(ItemError::TooPrecise(number.to_string()));
+    }
+    let fraction = format!("{:0<3}", fraction);
+    whole
+        .parse::<u64>()
+        .ok()
+        .and_then(|whole| whole.checked_mul(1000))
+        .and_then(|whole| whole.checked_add(fraction.parse().ok()?))
+        .ok_or_else(|| ItemError::TooLarge(number.to_string()))
+}
+
+/// Plurals that the suffix rules below get wrong, as (singular, plural).
+const IRREGULAR: [(&str, &str); 10] = [
+    ("child", "children"),
+    ("cookie", "cookies"),
+    ("foot", "feet"),
+    ("knife", "knives"),
+    ("leaf", "leaves"),
+    ("loaf", "loaves"),
+    ("mouse", "mice"),
+    ("person", "people"),
+    ("potato", "potatoes"),
+    ("tomato", "tomatoes"),
+];
+
+fn ends_in_sibilant(word: &str) -> bool {
+    ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|end| word.ends_with(end))
+}
+
+fn ends_in_consonant_y(word: &str) -> bool {
+    let mut letters = word.chars().rev();
+    letters.next() == Some('y') && letters.next().is_some_and(|c| !"aeiou".contains(c))
+}
+
+/// The singular of an English noun.
+pub fn singular(word: &str) -> String {
+    if let Some((singular, _)) = IRREGULAR.iter().find(|(_, plural)| *plural == word) {
+        return singular.to_string();
+    }
+    if let Some(stem) = word.strip_suffix("ies") {
+        if ends_in_consonant_y(&format!("{}y", stem)) && stem.len() > 1 {
+            return format!("{}y", stem);
+        }
+    }
+    if let Some(stem) = word.strip_suffix("es") {
+        // `houses` and `cheeses` come from words ending in `se`, which take
+        // just an `s`; `glasses` and `boxes` do not.
+        let single_s_or_z =
+            stem.ends_with(['s', 'z']) && !(stem.ends_with("ss") || stem.ends_with("zz"));
+        if plural(stem) == word && !single_s_or_z {
+            return stem.to_string();
+        }
+    }
+    match word.strip_suffix('s') {
+        // Leave words like `glass` and `asparagus` alone.
+        Some(stem) if !stem.is_empty() && !stem.ends_with(['s', 'u']) => stem.to_string(),
+        _ => word.to_string(),
+    }
+}
+
+/// The plural of an English noun.
+pub fn plural(word: &str) -> String {
+    if let Some((_, plural)) = IRREGULAR.iter().find(|(singular, _)| *singular == word) {
+        plural.to_string()
+    } else if ends_in_consonant_y(word) {
+        format!("{}ies", &word[..word.len() - 1])
+    } else if ends_in_sibilant(word) {
+        format!("{}es", word)
+    } else {
+        format!("{}s", word)
+    }
+}
+
+// Normalizes an item name: lowercase, single spaces, last word singular.
+fn normalize(item: &str) -> String {
+    let words: Vec<String> = item.split_whitespace().map(str::to_lowercase).collect();
+    match words.split_last() {
+        Some((last, rest)) => {
+            let mut words = rest.to_vec();
+            words.push(singular(last));
+            words.join(" ")
+        }
+        None => String::new(),
+    }
+}
+
+/// A parsed line such as `3 chairs` or `2.5 kg flour`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountItem {
+    pub quantity: Quantity,
+    /// The item name, lowercase and singular.
+    pub item: String,
+}
+
+impl FromStr for CountItem {
+    type Err = ItemError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        let (number, rest) = match text.split_once(char::is_whitespace) {
+            Some((number, rest)) => (number, rest.trim_start()),
+            None if text.is_empty() => return Err(ItemError::Empty),
+            None => return Err(ItemError::MissingItem(text.to_string())),
+        };
+        let thousandths = parse_thousandths(number)?;
+
+        // A unit is only a unit if an item follows it; `3 kg` alone is an error.
+        let unit = rest
+            .split_once(char::is_whitespace)
+            .and_then(|(word, item)| Some((Unit::from_word(word)?, item.trim_start())));
+        let (quantity, item) = match unit {
+            Some((unit, item)) => {
+                let thousandths = thousandths
+                    .checked_mul(unit.scale())
+                    .ok_or_else(|| ItemError::TooLarge(number.to_string()))?;
+                let quantity = Quantity {
+                    measure: unit.measure(),
+                    thousandths,
+                };
+                (quantity, item)
+            }
+            None => {
+                if Unit::from_word(rest).is_some() {
+                    return Err(ItemError::MissingItem(text.to_string()));
+                }
+                if thousandths % 1000 != 0 {
+                    return Err(ItemError::FractionalCount(number.to_string()));
+                }
+                let quantity = Quantity {
+                    measure: Measure::Count,
+                    thousandths,
+                };
+                (quantity, rest)
+            }
+        };
+        Ok(CountItem {
+            quantity,
+            item: normalize(item),
+        })
+    }
+}
+
+impl fmt::Display for CountItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.quantity.measure == Measure::Count && self.quantity.thousandths != 1000 {
+            let (rest, last) = match self.item.rsplit_once(' ') {
+                Some((rest, last)) => (format!("{} ", rest), last),
+                None => (String::new(), self.item.as_str()),
+            };
+            write!(f, "{} {}{}", self.quantity, rest, plural(last))
+        } else {
+            write!(f, "{} {}", self.quantity, self.item)
+        }
+    }
+}
+
+/// The total amount of every item on a list.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShoppingList {
+    totals: BTreeMap<String, Quantity>,
+}
+
+impl ShoppingList {
+    pub fn new() -> ShoppingList {
+        ShoppingList::default()
+    }
+
+    /// Parses one item per line, adding up repeated items. `#` starts a
+    /// comment line.
+    pub fn parse(text: &str) -> Result<ShoppingList, LoadError> {
+        let mut list = ShoppingList::new();
+        for (line, content) in content_lines(text) {
+            let syntax = |message| LoadError::Syntax { line, message };
+            let item: CountItem = content
+                .parse()
+                .map_err(|e: ItemError| syntax(e.to_string()))?;
+            list.add(item).map_err(syntax)?;
+        }
+        Ok(list)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ShoppingList, LoadError> {
+        ShoppingList::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Adds `item` to the list. Fails if the list already measures the same
+    /// item differently, like `2 onions` and `500 g onions`.
+    pub fn add(&mut self, item: CountItem) -> Result<(), String> {
+        match self.totals.get_mut(&item.item) {
+            Some(total) => {
+                *total = total.checked_add(item.quantity).ok_or_else(|| {
+                    if total.measure == item.quantity.measure {
+                        format!("total of {} is too large", item.item)
+                    } else {
+                        format!(
+                            "cannot add {} of {} to {} of {}",
+                            item.quantity.measure, item.item, total.measure, item.item
+                        )
+                    }
+                })?;
+            }
+            None => {
+                self.totals.insert(item.item, item.quantity);
+            }
+        }
+        Ok(())
+    }
+
+    /// The total of `item`, whose name must be normalized.
+    pub fn get(&self, item: &str) -> Option<Quantity> {
+        self.totals.get(item).copied()
+    }
+
+    /// Every item with its total, in alphabetical order.
+    pub fn items(&self) -> impl Iterator<Item = CountItem> + '_ {
+        self.totals.iter().map(|(item, &quantity)| CountItem {
+            quantity,
+            item: item.clone(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.totals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.totals.is_empty()
+    }
+}
+
+impl fmt::Display for ShoppingList {
+    /// One item per line, in a format [`ShoppingList::parse`] reads back.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in self.items() {
+            writeln!(f, "{}", item)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::property::assert_property;
+
+    #[test]
+    fn test_parse_count_item() {
+        let item: CountItem = "3 chairs".parse().unwrap();
+        assert_eq!(item.item, "chair");
+        assert_eq!(item.quantity.measure, Measure::Count);
+        assert_eq!(item.quantity.amount(), 3.0);
+
+        let item: CountItem = "  2.5   KG  plain Flour ".parse().unwrap();
+        assert_eq!(item.item, "plain flour");
+        assert_eq!(item.quantity.measure, Measure::Mass);
+        assert_eq!(item.quantity.amount(), 2500.0);
+        assert_eq!(item.to_string(), "2.5 kg plain flour");
+
+        assert_eq!(
+            "1 l milk".parse::<CountItem>().unwrap().to_string(),
+            "1 l milk"
+        );
+        assert_eq!(
+            "250 ml cream".parse::<CountItem>().unwrap().to_string(),
+            "250 ml cream"
+        );
+        assert_eq!(
+            "1 chair".parse::<CountItem>().unwrap().to_string(),
+            "1 chair"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |s: &str| s.parse::<CountItem>().unwrap_err().to_string();
+        assert_eq!(error(""), "expected `<count> [<unit>] <item>`, got nothing");
+        assert_eq!(error("3"), "no item after `3`");
+        assert_eq!(error("3 kg"), "no item after `3 kg`");
+        assert_eq!(error("three chairs"), "invalid number `three`");
+        assert_eq!(error("-1 chairs"), "invalid number `-1`");
+        assert_eq!(error(".5 kg flour"), "invalid number `.5`");
+        assert_eq!(
+            error("2.5 chairs"),
+            "cannot count `2.5` items, only whole ones"
+        );
+        assert_eq!(
+            error("0.0001 kg saffron"),
+            "`0.0001` has more than three decimal places"
+        );
+        assert_eq!(
+            error("99999999999999999 kg sand"),
+            "`99999999999999999` is too large"
+        );
+    }
+
+    #[test]
+    fn test_display_parses_back() {
+        assert_eq!(
+            "1000.5 g flour".parse::<CountItem>().unwrap().to_string(),
+            "1000.5 g flour"
+        );
+        assert_property(|(kilos, grams, milligrams): (u16, u16, u16)| {
+            let grams = u64::from(kilos) * 1000 + u64::from(grams % 1000);
+            for line in [
+                format!("{}.{:03} g flour", grams, milligrams % 1000),
+                format!("{}.{:03} ml milk", grams, milligrams % 1000),
+                format!("{} eggs", grams),
+            ] {
+                let item: CountItem = line.parse().unwrap();
+                let again: CountItem = item.to_string().parse().unwrap();
+                assert_eq!(again, item, "{} printed as {}", line, item);
+            }
+        });
+    }
+
+    #[test]
+    fn test_plurals_round_trip() {
+        for (one, many) in [
+            ("chair", "chairs"),
+            ("box", "boxes"),
+            ("peach", "peaches"),
+            ("berry", "berries"),
+            ("day", "days"),
+            ("tomato", "tomatoes"),
+            ("knife", "knives"),
+            ("glass", "glasses"),
+            ("cookie", "cookies"),
+            ("cheese", "cheeses"),
+            ("house", "houses"),
+        ] {
+            assert_eq!(plural(one), many);
+            assert_eq!(singular(many), one);
+            assert_eq!(singular(one), one);
+        }
+        let list = ShoppingList::parse("1 cheese\n2 cheeses\n").unwrap();
+        assert_eq!(list.to_string(), "3 cheeses\n");
+    }
+
+    #[test]
+    fn test_shopping_list() {
+        let list = ShoppingList::parse(
+            "# weekend\n\
+             2 Tomatoes\n\
+             1 tomato\n\
+             \n\
+             800 g rice\n\
+             0.4 kg rice\n\
+             1 loaf\n\
+             1.5 l milk\n\
+             500 ml milk\n",
+        )
+        .unwrap();
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.get("rice").unwrap().amount(), 1200.0);
+        assert_eq!(
+            list.to_string(),
+            "1 loaf\n2 l milk\n1.2 kg rice\n3 tomatoes\n"
+        );
+        assert_eq!(ShoppingList::parse(&list.to_string()).unwrap(), list);
+    }
+
+    #[test]
+    fn test_load_data_file() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/data/shopping.txt");
+        let list = ShoppingList::load(path).unwrap();
+        assert_eq!(
+            list.to_string(),
+            "3 chairs\n250 ml cream\n18 eggs\n3 kg flour\n1 l milk\n1 table\n3 tomatoes\n"
+        );
+    }
+
+    #[test]
+    fn test_shopping_list_errors_name_the_line() {
+        let error = ShoppingList::parse("3 chairs\n\n3 tables\nsome chairs\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 4: invalid number `some`");
+        let error = ShoppingList::parse("2 onions\n500 g onions\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: cannot add mass of onion to count of onion"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! How the example types are laid out in memory.
+//!
+//! `box_stack_and_heap::test` in `19_std_library_types.rs` prints
+//! `size_of_val` for a few values, and `literals` in `05_types.rs` does the
+//! same for numbers. A [`TypeLayout`] adds the alignment, the padding the
+//! compiler inserted between fields, and whether `Option<T>` is free: a
+//! type with a *niche*, a bit pattern no valid value uses (such as the null
+//! pointer for `Box`), lets `None` hide there instead of taking an extra
+//! tag.
+//!
+//! ```
+//! use rust_by_example::layout::TypeLayout;
+//!
+//! let boxed = TypeLayout::of::<Box<u64>>("Box<u64>");
+//! assert!(boxed.has_niche());
+//! assert!(!TypeLayout::of::<u64>("u64").has_niche());
+//! ```
+
+use std::{fmt, mem};
+
+/// The size, alignment and `Option` size of one type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeLayout {
+    pub name: String,
+    pub size: usize,
+    pub align: usize,
+    /// Bytes not used by any field, for types with known fields.
+    pub padding: Option<usize>,
+    pub option_size: usize,
+}
+
+impl TypeLayout {
+    /// The layout of `T`, shown as `name`.
+    pub fn of<T>(name: &str) -> TypeLayout {
+        TypeLayout {
+            name: name.to_string(),
+            size: mem::size_of::<T>(),
+            align: mem::align_of::<T>(),
+            padding: None,
+            option_size: mem::size_of::<Option<T>>(),
+        }
+    }
+
+    /// Records that the fields of the type take `bytes` bytes together,
+    /// and the rest of it is padding.
+    pub fn with_field_bytes(mut self, bytes: usize) -> TypeLayout {
+        // Fields larger than the type are a caller's mistake; the padding
+        // is then unknown.
+        self.padding = self.size.checked_sub(bytes);
+        self
+    }
+
+    /// Returns whether `Option<T>` is no larger than `T`.
+    pub fn has_niche(&self) -> bool {
+        self.option_size == self.size
+    }
+}
+
+// The layout of a type with fields of the given types.
+macro_rules! with_fields {
+    ($t:ty, $name:expr, $($field:ty),+) => {
+        TypeLayout::of::<$t>($name).with_field_bytes(0 $(+ mem::size_of::<$field>())+)
+    };
+}
+
+// The types below are the ones of the examples, which are binaries and so
+// cannot be named from here. Tests in the examples check that the copies
+// are laid out the same.
+
+/// `Point` of `19_std_library_types.rs`.
+#[derive(Debug, Clone, Copy)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// `Rectangle` of `19_std_library_types.rs`.
+#[derive(Debug, Clone, Copy)]
+pub struct Rectangle {
+    pub top_left: Point,
+    pub bottom_right: Point,
+}
+
+/// `Number` of `03_custom_types_enums.rs`, with implicit discriminants.
+#[derive(Debug, Clone, Copy)]
+pub enum Number {
+    Zero,
+    One,
+    Two,
+}
+
+/// `Color` of `03_custom_types_enums.rs`, with explicit discriminants.
+#[derive(Debug, Clone, Copy)]
+pub enum Color {
+    Red = 0xff0000,
+    Green = 0x00ff00,
+    Blue = 0x0000ff,
+}
+
+/// Layouts of a list of types, printed as a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registry {
+    layouts: Vec<TypeLayout>,
+}
+
+impl Registry {
+    pub fn new(layouts: Vec<TypeLayout>) -> Registry {
+        Registry { layouts }
+    }
+
+    /// The types of the examples, and the literals of `05_types.rs`.
+    pub fn examples() -> Registry {
+        Registry::new(vec![
+            TypeLayout::of::<u8>("u8"),
+            TypeLayout::of::<u32>("u32"),
+            TypeLayout::of::<i32>("i32"),
+            TypeLayout::of::<f32>("f32"),
+            TypeLayout::of::<f64>("f64"),
+            TypeLayout::of::<bool>("bool"),
+            TypeLayout::of::<char>("char"),
+            with_fields!((u8, u32), "(u8, u32)", u8, u32),
+            with_fields!(Point, "Point", f64, f64),
+            with_fields!(Rectangle, "Rectangle", Point, Point),
+            TypeLayout::of::<Box<Point>>("Box<Point>"),
+            TypeLayout::of::<Box<Rectangle>>("Box<Rectangle>"),
+            TypeLayout::of::<Box<Box<Point>>>("Box<Box<Point>>"),
+            TypeLayout::of::<Option<Box<Point>>>("Option<Box<Point>>"),
+            TypeLayout::of::<&str>("&str"),
+            TypeLayout::of::<String>("String"),
+            TypeLayout::of::<Vec<Point>>("Vec<Point>"),
+            TypeLayout::of::<Number>("Number"),
+            TypeLayout::of::<Color>("Color"),
+        ])
+    }
+
+    pub fn layouts(&self) -> &[TypeLayout] {
+        &self.layouts
+    }
+
+    /// The layout registered as `name`.
+    pub fn find(&self, name: &str) -> Option<&TypeLayout> {
+        self.layouts.iter().find(|layout| layout.name == name)
+    }
+}
+
+impl fmt::Display for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .layouts
+            .iter()
+            .map(|layout| layout.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+        writeln!(
+            f,
+            "{:<width$}  {:>5}  {:>5}  {:>7}  {:>9}",
+            "type", "size", "align", "padding", "Option<T>"
+        )?;
+        for layout in &self.layouts {
+            let padding = match layout.padding {
+                Some(padding) => padding.to_string(),
+                None => "-".to_string(),
+            };
+            let niche = if layout.has_niche() { " (niche)" } else { "" };
+            writeln!(
+                f,
+                "{:<width$}  {:>5}  {:>5}  {:>7}  {:>9}{}",
+                layout.name, layout.size, layout.align, padding, layout.option_size, niche
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_facts_on_every_platform() {
+        let registry = Registry::examples();
+        let layout = |name| registry.find(name).unwrap();
+        // True whatever the target.
+        for name in ["Box<Point>", "&str", "Number", "bool", "char"] {
+            assert!(layout(name).has_niche(), "{}", name);
+        }
+        assert_eq!(layout("Point").padding, Some(0));
+        assert_eq!(layout("Number").size, 1);
+        assert_eq!(layout("Box<Point>").size, mem::size_of::<usize>());
+        assert!(!layout("u32").has_niche());
+    }
+
+    #[test]
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn test_layouts_on_x86_64_linux() {
+        let registry = Registry::examples();
+        // (name, size, align, padding, Option size)
+        let expected: [(&str, usize, usize, Option<usize>, usize); 19] = [
+            ("u8", 1, 1, None, 2),
+            ("u32", 4, 4, None, 8),
+            ("i32", 4, 4, None, 8),
+            ("f32", 4, 4, None, 8),
+            ("f64", 8, 8, None, 16),
+            ("bool", 1, 1, None, 1),
+            ("char", 4, 4, None, 4),
+            ("(u8, u32)", 8, 4, Some(3), 12),
+            ("Point", 16, 8, Some(0), 24),
+            ("Rectangle", 32, 8, Some(0), 40),
+            ("Box<Point>", 8, 8, None, 8),
+            ("Box<Rectangle>", 8, 8, None, 8),
+            ("Box<Box<Point>>", 8, 8, None, 8),
+            ("Option<Box<Point>>", 8, 8, None, 16),
+            ("&str", 16, 8, None, 16),
+            ("String", 24, 8, None, 24),
+            ("Vec<Point>", 24, 8, None, 24),
+            ("Number", 1, 1, None, 1),
+            ("Color", 4, 4, None, 4),
+        ];
+        let actual: Vec<_> = registry
+            .layouts()
+            .iter()
+            .map(|l| (l.name.as_str(), l.size, l.align, l.padding, l.option_size))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_field_bytes_larger_than_the_type() {
+        let layout = TypeLayout::of::<u8>("u8").with_field_bytes(2);
+        assert_eq!(layout.padding, None);
+    }
+
+    #[test]
+    fn test_table() {
+        let registry = Registry::new(vec![
+            with_fields!((u8, u16), "(u8, u16)", u8, u16),
+            TypeLayout::of::<Box<u8>>("Box<u8>"),
+        ]);
+        let ptr = mem::size_of::<usize>();
+        assert_eq!(
+            registry.to_string(),
+            format!(
+                "type        size  align  padding  Option<T>\n\
+                 (u8, u16)      4      2        1          6\n\
+                 Box<u8>        {ptr}      {ptr}        -          {ptr} (niche)\n"
+            )
+        );
+    }
+}
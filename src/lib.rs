@@ -1,4 +1,28 @@
 // 21_testing_2_documentation_testing
+pub mod allocations;
+pub mod animals;
+pub mod arithmetic;
+pub mod bench;
+pub mod cast;
+pub mod contacts;
+pub mod events;
+pub mod examples;
+pub mod fizzbuzz;
+pub mod functional;
+pub mod i18n;
+pub mod interval;
+pub mod iter_ext;
+pub mod kitchen;
+pub mod layout;
+pub mod results;
+pub mod secret;
+pub mod testing;
+
+// Counts the allocations of every program that links to this crate.
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static ALLOCATOR: allocations::CountingAllocator = allocations::CountingAllocator;
+
 /// First line is a short summary describing function.
 ///
 /// The next lines present detailed documentation. Code blocks start with
@@ -26,16 +50,18 @@ pub fn add(a: i32, b: i32) -> i32 {
 ///
 /// # Panics
 ///
-/// The function panics if the second argument is zero.
+/// The function panics if the second argument is zero, or if the quotient
+/// does not fit in an `i32`, which only happens for `i32::MIN / -1`. Use
+/// [`arithmetic::checked_div`] to get an error instead.
 ///
 /// ```rust,should_panic
 /// // panics on division by zero
 /// rust_by_example::div(10, 0);
 /// ```
 pub fn div(a: i32, b: i32) -> i32 {
-    if b == 0 {
-        panic!("Divide-by-zero error");
+    match arithmetic::checked_div(a, b) {
+        Ok(quotient) => quotient,
+        Err(arithmetic::MathError::DivisionByZero) => panic!("Divide-by-zero error"),
+        Err(e) => panic!("Cannot divide {} by {}: {}", a, b, e),
     }
-
-    a / b
 }
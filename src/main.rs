@@ -1,3 +1,107 @@
+// An index of the examples in src/bin/: lists them by chapter, runs one by
+// number or name, or runs them all and prints a summary table.
+
+use rust_by_example::{
+    examples::{Example, ExampleError, ExampleIndex, Outcome, Summary, Verdict},
+    i18n::Catalog,
+};
+use std::{env, path::PathBuf, process, time::Duration};
+
+/// Runs the examples in this directory instead of `src/bin/`. The tests
+/// point it into a scratch copy of `data/`, which some examples write to.
+const RUN_DIR: &str = "RUST_BY_EXAMPLE_RUN_DIR";
+
+/// How long an example may run in `--all` mode.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn help() {
+    println!(
+        "usage:
+    rust_by_example [--list]
+        List the examples by chapter.
+    rust_by_example <number|name> [<args>...]
+        Run one example, passing it <args>. A number picks the example with
+        that number, not a chapter; any unique start of a name works.
+    rust_by_example --all
+        Run every example that is not interactive or slow, and summarize."
+    );
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
+}
+
+// Cargo puts every binary of the crate next to this one.
+fn bin_dir() -> PathBuf {
+    env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from))
+        .unwrap_or_default()
+}
+
+fn run(
+    example: &Example,
+    args: &[String],
+    timeout: Option<Duration>,
+) -> Result<Outcome, ExampleError> {
+    example.run_with(&bin_dir(), args, timeout, |command| {
+        if let Some(dir) = env::var_os(RUN_DIR) {
+            command.current_dir(dir);
+        }
+    })
+}
+
 fn main() {
-    println!("Hello, world!");
-}
\ No newline at end of file
+    let index = ExampleIndex::discover(ExampleIndex::DEFAULT_DIR).unwrap_or_else(|e| {
+        fail(&format!(
+            "could not read {}: {}",
+            ExampleIndex::DEFAULT_DIR,
+            e
+        ))
+    });
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        None | Some("--list") => {
+            // Set `LANG` (or `LC_ALL`) to e.g. `de_DE.UTF-8` to be greeted in German.
+            println!("{}\n", Catalog::from_env().get("hello.world", &[]));
+            print!("{}", index);
+        }
+        Some("-h" | "--help") => help(),
+        Some("--all") => {
+            let bin_dir = bin_dir();
+            if let Err(e) = index.ensure_built(&bin_dir) {
+                fail(&e.to_string());
+            }
+            let mut summary = Summary::default();
+            for example in index.examples() {
+                let verdict = match example.tag {
+                    Some(tag) => Verdict::Skipped(tag),
+                    None => match run(example, &[], Some(TIMEOUT)) {
+                        Ok(outcome) => Verdict::Ran(outcome),
+                        Err(e) => Verdict::Failed(e),
+                    },
+                };
+                summary.results.push((example.clone(), verdict));
+            }
+            print!("{}", summary);
+            if summary.failed() > 0 {
+                process::exit(1);
+            }
+        }
+        Some(query) => {
+            let example = index.find(query).unwrap_or_else(|e| fail(&e.to_string()));
+            let bin_dir = bin_dir();
+            if let Err(e) = index.ensure_built(&bin_dir) {
+                fail(&e.to_string());
+            }
+            let outcome = run(example, &args[1..], None)
+                .unwrap_or_else(|e| fail(&format!("could not run {}: {}", example.name, e)));
+            print!("{}", outcome.stdout);
+            eprint!("{}", outcome.stderr);
+            eprintln!("[{}: {}]", example.name, outcome.status);
+            process::exit(outcome.status.code().unwrap_or(1));
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Reusable strategies for handling a batch of `Result`s.
+//!
+//! `18_error_handling_6_iterating_over_results.rs` handles a list of
+//! `parse::<i32>()` results in five different ad hoc ways. [`ResultIterExt`]
+//! packages them as methods on any iterator of `Result`s. Every error comes
+//! back as an [`IndexedError`], which remembers the position of the item
+//! that failed, so a batch import can report which rows to fix.
+//!
+//! ```
+//! use rust_by_example::results::ResultIterExt;
+//!
+//! let strings = vec!["tofu", "93", "18"];
+//! let (numbers, errors) = strings.iter().map(|s| s.parse::<i32>()).partition_results();
+//! assert_eq!(numbers, [93, 18]);
+//! assert_eq!(errors[0].index, 0);
+//! assert_eq!(errors[0].to_string(), "item 0: invalid digit found in string");
+//! ```
+
+use std::{error, fmt};
+
+/// An error together with the position of the item that produced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedError<E> {
+    /// Zero-based position of the failed item in the input.
+    pub index: usize,
+    pub error: E,
+}
+
+impl<E: fmt::Display> fmt::Display for IndexedError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "item {}: {}", self.index, self.error)
+    }
+}
+
+impl<E: error::Error + 'static> error::Error for IndexedError<E> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// The successful values of a batch, and the errors that were set aside.
+pub type Partitioned<T, E> = (Vec<T>, Vec<IndexedError<E>>);
+
+/// Batch strategies for iterators over `Result`s.
+pub trait ResultIterExt<T, E>: Iterator<Item = Result<T, E>> + Sized {
+    /// Splits the batch into all successful values and all errors, like
+    /// `example_5`, without the `unwrap`s.
+    fn partition_results(self) -> Partitioned<T, E> {
+        let mut errors = Vec::new();
+        let values = self.collect_errors_into(&mut errors).collect();
+        (values, errors)
+    }
+
+    /// Yields the successful values and pushes every error into `errors`
+    /// as a side effect, like `example_3`.
+    ///
+    /// ```
+    /// use rust_by_example::results::ResultIterExt;
+    ///
+    /// let strings = vec!["42", "tofu", "93", "999", "18"];
+    /// let mut errors = vec![];
+    /// let numbers: Vec<u8> = strings
+    ///     .iter()
+    ///     .map(|s| s.parse::<u8>())
+    ///     .collect_errors_into(&mut errors)
+    ///     .collect();
+    /// assert_eq!(numbers, [42, 93, 18]);
+    /// assert_eq!(errors.iter().map(|e| e.index).collect::<Vec<_>>(), [1, 3]);
+    /// ```
+    fn collect_errors_into<'a>(
+        self,
+        errors: &'a mut Vec<IndexedError<E>>,
+    ) -> impl Iterator<Item = T> + 'a
+    where
+        Self: 'a,
+    {
+        self.enumerate().filter_map(move |(index, result)| {
+            result
+                .map_err(|error| errors.push(IndexedError { index, error }))
+                .ok()
+        })
+    }
+
+    /// Collects the batch, tolerating errors until `n_errors` of them have
+    /// been seen. The remaining items are not consumed once the limit is
+    /// reached. `fail_after(1)` fails fast, like `example_4`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n_errors` is zero.
+    ///
+    /// ```
+    /// use rust_by_example::results::ResultIterExt;
+    ///
+    /// let strings = vec!["1", "x", "2", "y", "z", "3"];
+    /// let parse = || strings.iter().map(|s| s.parse::<i32>());
+    ///
+    /// let (numbers, errors) = parse().fail_after(4).unwrap();
+    /// assert_eq!((numbers.len(), errors.len()), (3, 3));
+    /// let errors = parse().fail_after(2).unwrap_err();
+    /// assert_eq!(errors.iter().map(|e| e.index).collect::<Vec<_>>(), [1, 3]);
+    /// ```
+    fn fail_after(self, n_errors: usize) -> Result<Partitioned<T, E>, Vec<IndexedError<E>>> {
+        assert!(n_errors != 0, "the error limit must be at least 1");
+        let mut values = Vec::new();
+        let mut errors = Vec::new();
+        for (index, result) in self.enumerate() {
+            match result {
+                Ok(value) => values.push(value),
+                Err(error) => {
+                    errors.push(IndexedError { index, error });
+                    if errors.len() == n_errors {
+                        return Err(errors);
+                    }
+                }
+            }
+        }
+        Ok((values, errors))
+    }
+
+    /// Collects every value, or returns the first error and where it
+    /// happened. The remaining items are not consumed after an error.
+    fn first_error_with_index(self) -> Result<Vec<T>, IndexedError<E>> {
+        self.enumerate()
+            .map(|(index, result)| result.map_err(|error| IndexedError { index, error }))
+            .collect()
+    }
+}
+
+impl<T, E, I: Iterator<Item = Result<T, E>>> ResultIterExt<T, E> for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::num::ParseIntError;
+
+    fn parse<'a>(strings: &'a [&'a str]) -> impl Iterator<Item = Result<i32, ParseIntError>> + 'a {
+        strings.iter().map(|s| s.parse::<i32>())
+    }
+
+    fn indices<E>(errors: &[IndexedError<E>]) -> Vec<usize> {
+        errors.iter().map(|e| e.index).collect()
+    }
+
+    #[test]
+    fn test_partition_results() {
+        let (numbers, errors) = parse(&["tofu", "93", "18"]).partition_results();
+        assert_eq!(numbers, [93, 18]);
+        assert_eq!(indices(&errors), [0]);
+
+        let (numbers, errors) = parse(&[]).partition_results();
+        assert!(numbers.is_empty() && errors.is_empty());
+    }
+
+    #[test]
+    fn test_collect_errors_into_appends() {
+        let mut errors = vec![];
+        let first: Vec<_> = parse(&["a", "1"])
+            .collect_errors_into(&mut errors)
+            .collect();
+        let second: Vec<_> = parse(&["2", "b"])
+            .collect_errors_into(&mut errors)
+            .collect();
+        assert_eq!((first, second), (vec![1], vec![2]));
+        // Indices are relative to the batch each error came from.
+        assert_eq!(indices(&errors), [0, 1]);
+    }
+
+    #[test]
+    fn test_fail_after() {
+        let strings = ["x", "1", "y", "2", "z"];
+        assert_eq!(indices(&parse(&strings).fail_after(1).unwrap_err()), [0]);
+        assert_eq!(
+            indices(&parse(&strings).fail_after(3).unwrap_err()),
+            [0, 2, 4]
+        );
+        let (numbers, errors) = parse(&strings).fail_after(4).unwrap();
+        assert_eq!((numbers, indices(&errors)), (vec![1, 2], vec![0, 2, 4]));
+    }
+
+    #[test]
+    fn test_fail_after_stops_consuming() {
+        let mut consumed = 0;
+        let result = parse(&["x", "1", "2"])
+            .inspect(|_| consumed += 1)
+            .fail_after(1);
+        assert!(result.is_err());
+        assert_eq!(consumed, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 1")]
+    fn test_fail_after_zero() {
+        let _ = parse(&["1"]).fail_after(0);
+    }
+
+    #[test]
+    fn test_first_error_with_index() {
+        assert_eq!(
+            parse(&["93", "18"]).first_error_with_index(),
+            Ok(vec![93, 18])
+        );
+        let error = parse(&["93", "tofu", "x"])
+            .first_error_with_index()
+            .unwrap_err();
+        assert_eq!(error.index, 1);
+        assert_eq!(error.to_string(), "item 1: invalid digit found in string");
+        assert!(error::Error::source(&error).is_some());
+    }
+}
@@ -0,0 +1,348 @@
+//! A wrapper for values that must not leak.
+//!
+//! `ClosedBox<T>` in `10_modules_struct_visibility.rs` hides its contents
+//! from other modules, but that is all it does. [`Secret<T>`] goes further:
+//!
+//! * `Debug` and `Display` print `[REDACTED]`, so secrets stay out of logs,
+//! * reading the value takes an explicit, greppable [`expose_secret`] call,
+//! * byte and string payloads are overwritten with zeros when dropped,
+//! * comparing two secrets takes the same time wherever they differ.
+//!
+//! [`expose_secret`]: Secret::expose_secret
+//!
+//! ```
+//! use rust_by_example::secret::Secret;
+//!
+//! let password = Secret::new(String::from("hunter2"));
+//! assert_eq!(format!("{:?}", password), "[REDACTED]");
+//! assert_eq!(password.expose_secret(), "hunter2");
+//! ```
+
+use std::{
+    env, error, fmt, fs, hint,
+    io::{self, Read},
+    path::Path,
+    ptr,
+    sync::atomic::{compiler_fence, Ordering},
+};
+
+/// Values that can overwrite their own memory.
+pub trait Zeroize {
+    /// Overwrites every byte this value owns with zeros, in a way the
+    /// optimizer may not remove.
+    fn zeroize(&mut self);
+}
+
+fn zero_bytes(bytes: &mut [u8]) {
+    for byte in bytes {
+        // Volatile writes are never optimized away, even right before a free.
+        // SAFETY: `byte` comes from a `&mut [u8]`, so it is valid, aligned
+        // and not aliased.
+        unsafe { ptr::write_volatile(byte, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+impl Zeroize for Vec<u8> {
+    fn zeroize(&mut self) {
+        zero_bytes(self);
+        self.clear();
+        // Old contents may also linger in the unused capacity.
+        let spare = self.spare_capacity_mut();
+        for byte in spare {
+            // SAFETY: the spare capacity is allocated, writable memory owned
+            // by this vector, and writing a `u8` initializes the byte.
+            unsafe { ptr::write_volatile(byte.as_mut_ptr(), 0) };
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl Zeroize for String {
+    fn zeroize(&mut self) {
+        // Zero bytes are valid UTF-8, so the string stays valid throughout.
+        let mut bytes = std::mem::take(self).into_bytes();
+        bytes.zeroize();
+        *self = String::from_utf8(bytes).expect("an empty buffer is valid UTF-8");
+    }
+}
+
+impl Zeroize for Box<[u8]> {
+    fn zeroize(&mut self) {
+        zero_bytes(self);
+    }
+}
+
+impl<const N: usize> Zeroize for [u8; N] {
+    fn zeroize(&mut self) {
+        zero_bytes(self);
+    }
+}
+
+/// A value that is redacted when formatted and zeroed when dropped.
+pub struct Secret<T: Zeroize> {
+    value: T,
+}
+
+impl<T: Zeroize> Secret<T> {
+    pub fn new(value: T) -> Secret<T> {
+        Secret { value }
+    }
+
+    /// Gives access to the secret value. Every read of a secret goes
+    /// through here, which makes them easy to audit.
+    pub fn expose_secret(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+impl<T: Zeroize> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl<T: Zeroize> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl<T: Zeroize + Clone> Clone for Secret<T> {
+    fn clone(&self) -> Self {
+        Secret::new(self.value.clone())
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Secret::new(value)
+    }
+}
+
+/// Compares two byte strings in time that depends only on their lengths,
+/// not on where they differ.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let difference = a
+        .iter()
+        .zip(b)
+        .fold(0u8, |acc, (x, y)| acc | hint::black_box(x ^ y));
+    hint::black_box(difference) == 0
+}
+
+impl<T: Zeroize + AsRef<[u8]>> Secret<T> {
+    /// Compares the secret with `other` in constant time. Only the length
+    /// of the secret can be learned from timing.
+    ///
+    /// ```
+    /// use rust_by_example::secret::Secret;
+    ///
+    /// let token = Secret::new(b"s3cr3t".to_vec());
+    /// assert!(token.ct_eq(b"s3cr3t"));
+    /// assert!(!token.ct_eq(b"s3cr3T"));
+    /// ```
+    pub fn ct_eq(&self, other: &[u8]) -> bool {
+        constant_time_eq(self.value.as_ref(), other)
+    }
+}
+
+impl<T: Zeroize + AsRef<[u8]>> PartialEq for Secret<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other.value.as_ref())
+    }
+}
+
+impl<T: Zeroize + AsRef<[u8]>> Eq for Secret<T> {}
+
+/// The errors that can occur while loading a secret.
+#[derive(Debug)]
+pub enum SecretError {
+    /// The environment variable is not set.
+    NotPresent(String),
+    /// The environment variable is not valid Unicode.
+    NotUnicode(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretError::NotPresent(var) => write!(f, "environment variable {} is not set", var),
+            SecretError::NotUnicode(var) => {
+                write!(f, "environment variable {} is not valid unicode", var)
+            }
+            SecretError::Io(e) => write!(f, "could not read secret: {}", e),
+        }
+    }
+}
+
+impl error::Error for SecretError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SecretError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SecretError {
+    fn from(e: io::Error) -> Self {
+        SecretError::Io(e)
+    }
+}
+
+impl Secret<String> {
+    /// Reads the secret from the environment variable `var`.
+    ///
+    /// Only the returned copy is zeroed on drop. The variable itself stays
+    /// in the process environment until it is removed with
+    /// [`env::remove_var`].
+    pub fn from_env(var: &str) -> Result<Secret<String>, SecretError> {
+        match env::var(var) {
+            Ok(value) => Ok(Secret::new(value)),
+            Err(env::VarError::NotPresent) => Err(SecretError::NotPresent(var.to_string())),
+            Err(env::VarError::NotUnicode(_)) => Err(SecretError::NotUnicode(var.to_string())),
+        }
+    }
+
+    /// Reads the secret from a file, dropping one trailing line break.
+    ///
+    /// The contents are read into a buffer sized from the file's metadata,
+    /// so no partial copies are left behind by reallocation, and the buffer
+    /// is zeroed if reading fails or the file is not valid UTF-8. A file
+    /// that grows while it is read may still force a reallocation.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Secret<String>, SecretError> {
+        let mut file = fs::File::open(path)?;
+        let size = file.metadata().map(|m| m.len() as usize).unwrap_or(0);
+        // One spare byte lets `read_to_end` see the end of file without growing.
+        let mut bytes = Secret::new(Vec::with_capacity(size + 1));
+        file.read_to_end(&mut bytes.value)?;
+        let value = match String::from_utf8(std::mem::take(&mut bytes.value)) {
+            Ok(value) => value,
+            Err(e) => {
+                bytes.value = e.into_bytes();
+                return Err(SecretError::Io(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "stream did not contain valid UTF-8",
+                )));
+            }
+        };
+        let mut secret = Secret::new(value);
+        let value = &mut secret.value;
+        if value.ends_with('\n') {
+            value.pop();
+            if value.ends_with('\r') {
+                value.pop();
+            }
+        }
+        Ok(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formatting_never_leaks() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Config {
+            user: &'static str,
+            password: Secret<String>,
+        }
+
+        let password = Secret::new(String::from("hunter2"));
+        for formatted in [
+            format!("{}", password),
+            format!("{:?}", password),
+            format!("{:>20}", password),
+            format!("{:#?}", password),
+        ] {
+            assert!(!formatted.contains("hunter2"));
+            assert!(formatted.contains("[REDACTED]"));
+        }
+
+        let config = Config {
+            user: "ferris",
+            password,
+        };
+        let formatted = format!("{:?} {:#?}", config, config);
+        assert!(formatted.contains("ferris"));
+        assert!(!formatted.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_zeroize_overwrites_bytes() {
+        let mut bytes = vec![1u8, 2, 3, 4];
+        bytes.truncate(2);
+        bytes.zeroize();
+        assert!(bytes.is_empty());
+        // The truncated tail lives on in the spare capacity; it must be gone too.
+        assert!(bytes.capacity() >= 4);
+        let tail: Vec<u8> = bytes
+            .spare_capacity_mut()
+            .iter()
+            // SAFETY: `zeroize` wrote a zero to every spare byte.
+            .map(|b| unsafe { b.assume_init() })
+            .collect();
+        assert!(tail.iter().all(|&b| b == 0));
+
+        let mut array = *b"key!";
+        array.zeroize();
+        assert_eq!(array, [0; 4]);
+
+        let mut string = String::from("hunter2");
+        let ptr = string.as_ptr();
+        string.zeroize();
+        assert!(string.is_empty());
+        // The same allocation is kept, so nothing was copied elsewhere.
+        assert_eq!(string.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+        let a = Secret::new(String::from("token"));
+        assert_eq!(a, a.clone());
+        assert_ne!(a, Secret::new(String::from("t0ken")));
+    }
+
+    #[test]
+    fn test_loading() {
+        let var = "RUST_BY_EXAMPLE_SECRET_TEST_UNSET";
+        let error = Secret::from_env(var).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("environment variable {} is not set", var)
+        );
+        // PATH is set in every test environment.
+        assert!(Secret::from_env("PATH").is_ok());
+
+        let path = env::temp_dir().join(format!("rbe-secret-{}", std::process::id()));
+        fs::write(&path, "hunter2\r\n").unwrap();
+        let secret = Secret::from_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(secret.unwrap().expose_secret(), "hunter2");
+        assert!(matches!(Secret::from_file(&path), Err(SecretError::Io(_))));
+
+        fs::write(&path, b"hunter\xff").unwrap();
+        let secret = Secret::from_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(
+            matches!(secret, Err(SecretError::Io(e)) if e.kind() == io::ErrorKind::InvalidData)
+        );
+    }
+}
@@ -0,0 +1,266 @@
+//! Assertions for the tests of chapter 21 that `assert_eq!` and
+//! `#[should_panic]` do not cover.
+//!
+//! `test_sqrt` in `21_testing_1_unit_testing.rs` compares floats with `==`,
+//! which only holds because 4 is a perfect square. [`assert_approx_eq!`]
+//! allows for rounding instead, within an absolute, relative or ULP
+//! tolerance. [`assert_panics`] is `#[should_panic(expected = ...)]` as a
+//! function, so one test can check several panics and go on afterwards.
+//! [`property`] checks claims on random inputs.
+//!
+//! ```
+//! use rust_by_example::{assert_approx_eq, testing::assert_panics};
+//!
+//! assert_approx_eq!(0.1 + 0.2, 0.3);
+//! assert_approx_eq!(2f64.sqrt().powi(2), 2.0, abs = 1e-12);
+//!
+//! let message = assert_panics(|| rust_by_example::div(1, 0), "Divide*zero");
+//! assert_eq!(message, "Divide-by-zero error");
+//! ```
+
+pub mod property;
+
+use std::{
+    any::Any,
+    fmt,
+    panic::{self, UnwindSafe},
+};
+
+/// How far apart two floats may be and still count as equal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tolerance {
+    /// At most this far apart.
+    Abs(f64),
+    /// At most this fraction of the larger magnitude apart.
+    Rel(f64),
+    /// At most this many representable `f64`s apart.
+    Ulps(u64),
+}
+
+impl Tolerance {
+    // Lower-case constructors for the `abs = ...` syntax of the macro.
+    pub fn abs(max: f64) -> Tolerance {
+        Tolerance::Abs(max)
+    }
+
+    pub fn rel(max: f64) -> Tolerance {
+        Tolerance::Rel(max)
+    }
+
+    pub fn ulps(max: u64) -> Tolerance {
+        Tolerance::Ulps(max)
+    }
+
+    /// Returns whether `a` and `b` are within this tolerance of each other.
+    pub fn allows(self, a: f64, b: f64) -> bool {
+        match self {
+            Tolerance::Abs(max) => (a - b).abs() <= max,
+            Tolerance::Rel(max) => relative_difference(a, b) <= max,
+            Tolerance::Ulps(max) => ulps_between(a, b).is_some_and(|ulps| ulps <= max),
+        }
+    }
+}
+
+impl fmt::Display for Tolerance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tolerance::Abs(max) => write!(f, "abs <= {:e}", max),
+            Tolerance::Rel(max) => write!(f, "rel <= {:e}", max),
+            Tolerance::Ulps(max) => write!(f, "ulps <= {}", max),
+        }
+    }
+}
+
+/// `|a - b|` as a fraction of the larger of `|a|` and `|b|`.
+pub fn relative_difference(a: f64, b: f64) -> f64 {
+    if a == b {
+        return 0.0;
+    }
+    (a - b).abs() / a.abs().max(b.abs())
+}
+
+/// The number of representable `f64`s from `a` to `b`, or `None` if
+/// either is NaN. `-0.0` and `0.0` are the same number.
+///
+/// ```
+/// use rust_by_example::testing::ulps_between;
+///
+/// assert_eq!(ulps_between(1.0, 1.0 + f64::EPSILON), Some(1));
+/// assert_eq!(ulps_between(-0.0, f64::from_bits(1)), Some(1));
+/// assert_eq!(ulps_between(f64::NAN, 1.0), None);
+/// ```
+pub fn ulps_between(a: f64, b: f64) -> Option<u64> {
+    if a.is_nan() || b.is_nan() {
+        return None;
+    }
+    // Maps the floats onto integers in the same order, with both zeros at 0.
+    let ordered = |x: f64| {
+        let bits = x.to_bits() as i64;
+        i128::from(if bits < 0 { i64::MIN - bits } else { bits })
+    };
+    Some((ordered(a) - ordered(b)).unsigned_abs() as u64)
+}
+
+/// Checks `left` against `right` for [`assert_approx_eq!`], which passes
+/// if any of `tolerances` allows the difference. Returns the failure
+/// message otherwise.
+pub fn approx_eq(left: f64, right: f64, tolerances: &[Tolerance]) -> Result<(), String> {
+    // Equal infinities are as close as it gets, but their difference is NaN.
+    if left == right || tolerances.iter().any(|t| t.allows(left, right)) {
+        return Ok(());
+    }
+    let ulps = match ulps_between(left, right) {
+        Some(ulps) => ulps.to_string(),
+        None => "no".to_string(),
+    };
+    let allowed: Vec<String> = tolerances.iter().map(|t| t.to_string()).collect();
+    Err(format!(
+        "assertion `left ≈ right` failed\n   left: {:?}\n  right: {:?}\n   diff: {:e} (rel {:e}, {} ulps)\nallowed: {}",
+        left,
+        right,
+        (left - right).abs(),
+        relative_difference(left, right),
+        ulps,
+        allowed.join(" or ")
+    ))
+}
+
+/// Asserts that two floats are equal up to rounding.
+///
+/// Without tolerances, the floats may be 4 ULPs apart. Otherwise the
+/// assertion passes if any of the tolerances, given as `abs = ...`,
+/// `rel = ...` or `ulps = ...`, allows the difference. Both sides are
+/// compared as `f64`s.
+///
+/// ```
+/// use rust_by_example::assert_approx_eq;
+///
+/// assert_approx_eq!(1e-20, 0.0, abs = 1e-12);
+/// assert_approx_eq!(1000.0, 1000.1, rel = 1e-3, abs = 1e-9);
+/// ```
+///
+/// ```should_panic
+/// rust_by_example::assert_approx_eq!(1.0, 1.1, ulps = 100);
+/// ```
+#[macro_export]
+macro_rules! assert_approx_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_approx_eq!($left, $right, ulps = 4)
+    };
+    ($left:expr, $right:expr, $($kind:ident = $max:expr),+ $(,)?) => {
+        if let Err(message) = $crate::testing::approx_eq(
+            f64::from($left),
+            f64::from($right),
+            &[$($crate::testing::Tolerance::$kind($max)),+],
+        ) {
+            panic!("{}", message);
+        }
+    };
+}
+
+// Panic payloads are `&str` for literal messages and `String` otherwise.
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "<non-string panic payload>".to_string()
+    }
+}
+
+/// Runs `f` and returns the message it panicked with, or `None` if it
+/// returned normally.
+///
+/// The panic is still reported on stderr by the panic hook, as in any test.
+pub fn panic_message<R>(f: impl FnOnce() -> R + UnwindSafe) -> Option<String> {
+    panic::catch_unwind(f)
+        .err()
+        .map(|payload| payload_message(&*payload))
+}
+
+/// Returns whether `message` contains `pattern`, where a `*` in `pattern`
+/// stands for any text.
+///
+/// ```
+/// use rust_by_example::testing::matches_pattern;
+///
+/// assert!(matches_pattern("Divide-by-zero error", "zero"));
+/// assert!(matches_pattern("Divide-by-zero error", "Div*error"));
+/// assert!(!matches_pattern("Divide-by-zero error", "error*zero"));
+/// ```
+pub fn matches_pattern(message: &str, pattern: &str) -> bool {
+    let mut rest = message;
+    for piece in pattern.split('*') {
+        match rest.find(piece) {
+            Some(i) => rest = &rest[i + piece.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Asserts that `f` panics with a message matching `pattern`, in the sense
+/// of [`matches_pattern`], and returns the message.
+#[track_caller]
+pub fn assert_panics<R>(f: impl FnOnce() -> R + UnwindSafe, pattern: &str) -> String {
+    match panic_message(f) {
+        Some(message) if matches_pattern(&message, pattern) => message,
+        Some(message) => panic!(
+            "panic message does not match\nmessage: {:?}\npattern: {:?}",
+            message, pattern
+        ),
+        None => panic!("expected a panic matching {:?}, but none happened", pattern),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tolerances() {
+        assert!(Tolerance::Abs(0.5).allows(1.0, 1.5));
+        assert!(!Tolerance::Abs(0.5).allows(1.0, 1.6));
+        assert!(Tolerance::Rel(0.01).allows(100.0, 101.0));
+        assert!(!Tolerance::Rel(0.01).allows(1.0, 1.02));
+        assert!(Tolerance::Ulps(2).allows(1.0, 1.0 + 2.0 * f64::EPSILON));
+        assert!(!Tolerance::Ulps(2).allows(f64::NAN, f64::NAN));
+        // The largest finite float is one ULP below infinity.
+        assert_eq!(ulps_between(f64::MAX, f64::INFINITY), Some(1));
+        assert_eq!(ulps_between(-1.0, 1.0), Some(2 * 1.0f64.to_bits()));
+    }
+
+    #[test]
+    fn test_approx_eq_failure_message() {
+        assert_eq!(approx_eq(f64::INFINITY, f64::INFINITY, &[]), Ok(()));
+        let message = approx_eq(1.0, 1.5, &[Tolerance::Abs(0.1), Tolerance::Ulps(4)]).unwrap_err();
+        assert_eq!(
+            message,
+            "assertion `left ≈ right` failed\n   left: 1.0\n  right: 1.5\n   \
+             diff: 5e-1 (rel 3.333333333333333e-1, 2251799813685248 ulps)\n\
+             allowed: abs <= 1e-1 or ulps <= 4"
+        );
+        assert!(approx_eq(f64::NAN, 1.0, &[Tolerance::Abs(1.0)])
+            .unwrap_err()
+            .contains("no ulps"));
+    }
+
+    #[test]
+    fn test_assert_panics() {
+        assert_eq!(panic_message(|| 1), None);
+        assert_eq!(
+            panic_message(|| panic!("{} apples", 3)).as_deref(),
+            Some("3 apples")
+        );
+        assert_eq!(assert_panics(|| panic!("no apples"), "apples"), "no apples");
+
+        let message = panic_message(|| assert_panics(|| panic!("no apples"), "pears"));
+        assert!(message.unwrap().starts_with("panic message does not match"));
+        let message = panic_message(|| assert_panics(|| (), "pears"));
+        assert_eq!(
+            message.unwrap(),
+            "expected a panic matching \"pears\", but none happened"
+        );
+    }
+}
@@ -0,0 +1,574 @@
+//! Property-based testing: checking a claim on many random inputs instead
+//! of a few hand-picked ones.
+//!
+//! `test_add` in `21_testing_1_unit_testing.rs` checks `add(100, 2)`. A
+//! property says what must hold for every input, such as `add(a, b) ==
+//! add(b, a)`, and [`assert_property`] tries it on a few hundred inputs
+//! that [`Arbitrary`] makes up. When one fails, it is shrunk to a smallest
+//! failing input, and the failure names the seed that replays the run. For
+//! `|x: u32| x < 1000`:
+//!
+//! ```text
+//! property failed on case 4 of 256; replay with PROPERTY_SEED=0x1b67e0d4a9c3f102
+//!  minimal input: 1000 (after 26 shrinks)
+//! original input: 3052169568
+//!         reason: returned false
+//! ```
+//!
+//! ```
+//! use rust_by_example::testing::property::{assert_property, check, Config};
+//!
+//! assert_property(|(a, b): (i16, i16)| i32::from(a) + i32::from(b) == i32::from(b) + i32::from(a));
+//!
+//! let failure = check(Config::with_seed(7), |x: u32| x < 1000).unwrap_err();
+//! assert_eq!(failure.minimal, 1000);
+//! ```
+
+use std::{
+    env, fmt,
+    panic::{self, AssertUnwindSafe},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The environment variable that fixes the seed of [`Config::from_env`].
+pub const SEED_VAR: &str = "PROPERTY_SEED";
+
+/// A small, seedable pseudo-random number generator (xorshift64*).
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // Xorshift gets stuck on 0.
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A number in `0..n`, or 0 if `n` is 0.
+    pub fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            0
+        } else {
+            self.next_u64() % n
+        }
+    }
+
+    /// `true` about once in `n` times.
+    pub fn one_in(&mut self, n: u64) -> bool {
+        self.below(n) == 0
+    }
+}
+
+/// A type whose values can be made up at random and made simpler.
+pub trait Arbitrary: Clone + fmt::Debug {
+    /// A random value. `size` grows from 0 over a run, and bounds the
+    /// magnitude of numbers and the length of collections most of the time.
+    fn arbitrary(rng: &mut Rng, size: usize) -> Self;
+
+    /// Simpler values to try in place of a failing one, simplest first.
+    fn shrink(&self) -> Vec<Self> {
+        Vec::new()
+    }
+}
+
+impl Arbitrary for bool {
+    fn arbitrary(rng: &mut Rng, _size: usize) -> bool {
+        rng.one_in(2)
+    }
+
+    fn shrink(&self) -> Vec<bool> {
+        if *self {
+            vec![false]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+// Values between 0 and `x` that close in on `x`: 0, x/2, 3x/4, ..., x - 1.
+// Trying them in order finds the smallest failing value in a few steps.
+fn towards_zero(x: i128) -> Vec<i128> {
+    let mut candidates = Vec::new();
+    let mut gap = x;
+    while gap != 0 {
+        candidates.push(x - gap);
+        gap /= 2;
+    }
+    if x < 0 {
+        candidates.insert(1, -x);
+    }
+    candidates
+}
+
+macro_rules! arbitrary_integer {
+    ($($t:ty)*) => {$(
+        impl Arbitrary for $t {
+            fn arbitrary(rng: &mut Rng, size: usize) -> $t {
+                const EDGES: [$t; 4] = [0, 1, <$t>::MIN, <$t>::MAX];
+                if rng.one_in(10) {
+                    EDGES[rng.below(4) as usize]
+                } else if rng.one_in(10) {
+                    // Any value at all; truncation keeps the low bits.
+                    rng.next_u64() as $t
+                } else {
+                    let span = size as i128 + 1;
+                    let lowest = i128::from(<$t>::MIN).max(-span);
+                    let highest = i128::from(<$t>::MAX).min(span);
+                    let n = lowest + i128::from(rng.below((highest - lowest + 1) as u64));
+                    n as $t
+                }
+            }
+
+            fn shrink(&self) -> Vec<$t> {
+                towards_zero(i128::from(*self))
+                    .into_iter()
+                    .filter_map(|n| <$t>::try_from(n).ok())
+                    .collect()
+            }
+        }
+    )*};
+}
+
+arbitrary_integer!(u8 u16 u32 u64 i8 i16 i32 i64);
+
+impl Arbitrary for usize {
+    fn arbitrary(rng: &mut Rng, size: usize) -> usize {
+        u64::arbitrary(rng, size) as usize
+    }
+
+    fn shrink(&self) -> Vec<usize> {
+        (*self as u64)
+            .shrink()
+            .into_iter()
+            .map(|n| n as usize)
+            .collect()
+    }
+}
+
+impl Arbitrary for f64 {
+    /// Mostly finite numbers within `size` of 0, sometimes an infinity,
+    /// NaN or an extreme.
+    fn arbitrary(rng: &mut Rng, size: usize) -> f64 {
+        const EDGES: [f64; 7] = [
+            0.0,
+            -0.0,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NAN,
+            f64::MIN_POSITIVE,
+            f64::MAX,
+        ];
+        if rng.one_in(20) {
+            return EDGES[rng.below(EDGES.len() as u64) as usize];
+        }
+        let unit = (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        (unit * 2.0 - 1.0) * (size as f64 + 1.0)
+    }
+
+    fn shrink(&self) -> Vec<f64> {
+        let x = *self;
+        let mut candidates = Vec::new();
+        for candidate in [0.0, x.trunc(), x / 2.0] {
+            if candidate != x && candidate.is_finite() && !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+        candidates
+    }
+}
+
+impl Arbitrary for char {
+    /// Mostly printable ASCII, sometimes any `char`.
+    fn arbitrary(rng: &mut Rng, _size: usize) -> char {
+        if rng.one_in(4) {
+            loop {
+                if let Some(c) = char::from_u32(rng.below(0x11_0000) as u32) {
+                    return c;
+                }
+            }
+        }
+        char::from(b' ' + rng.below(95) as u8)
+    }
+
+    fn shrink(&self) -> Vec<char> {
+        ['a', 'b', 'A', ' ']
+            .into_iter()
+            .take_while(|c| c != self)
+            .collect()
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Vec<T> {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Vec<T> {
+        let len = rng.below(size as u64 + 1);
+        (0..len).map(|_| T::arbitrary(rng, size)).collect()
+    }
+
+    /// Shorter vectors first, then ones with a simpler element.
+    fn shrink(&self) -> Vec<Vec<T>> {
+        let mut candidates = Vec::new();
+        if self.is_empty() {
+            return candidates;
+        }
+        candidates.push(Vec::new());
+        let half = self.len() / 2;
+        if half > 0 {
+            candidates.push(self[..half].to_vec());
+            candidates.push(self[half..].to_vec());
+        }
+        for i in 0..self.len() {
+            if self.len() > 1 {
+                let mut shorter = self.clone();
+                shorter.remove(i);
+                candidates.push(shorter);
+            }
+        }
+        for (i, item) in self.iter().enumerate() {
+            for simpler in item.shrink() {
+                let mut candidate = self.clone();
+                candidate[i] = simpler;
+                candidates.push(candidate);
+            }
+        }
+        candidates
+    }
+}
+
+impl Arbitrary for String {
+    fn arbitrary(rng: &mut Rng, size: usize) -> String {
+        Vec::<char>::arbitrary(rng, size).into_iter().collect()
+    }
+
+    fn shrink(&self) -> Vec<String> {
+        let chars: Vec<char> = self.chars().collect();
+        chars
+            .shrink()
+            .into_iter()
+            .map(|chars| chars.into_iter().collect())
+            .collect()
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Option<T> {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Option<T> {
+        if rng.one_in(4) {
+            None
+        } else {
+            Some(T::arbitrary(rng, size))
+        }
+    }
+
+    fn shrink(&self) -> Vec<Option<T>> {
+        match self {
+            None => Vec::new(),
+            Some(value) => std::iter::once(None)
+                .chain(value.shrink().into_iter().map(Some))
+                .collect(),
+        }
+    }
+}
+
+// A tuple shrinks one component at a time, keeping the others.
+macro_rules! arbitrary_tuple {
+    ($(($($name:ident $index:tt),+))*) => {$(
+        impl<$($name: Arbitrary),+> Arbitrary for ($($name,)+) {
+            fn arbitrary(rng: &mut Rng, size: usize) -> Self {
+                ($($name::arbitrary(rng, size),)+)
+            }
+
+            fn shrink(&self) -> Vec<Self> {
+                let mut candidates = Vec::new();
+                $(
+                    for simpler in self.$index.shrink() {
+                        let mut candidate = self.clone();
+                        candidate.$index = simpler;
+                        candidates.push(candidate);
+                    }
+                )+
+                candidates
+            }
+        }
+    )*};
+}
+
+arbitrary_tuple! {
+    (A 0)
+    (A 0, B 1)
+    (A 0, B 1, C 2)
+    (A 0, B 1, C 2, D 3)
+}
+
+/// What a property can return: `bool`, `()` or a `Result`. A panic in the
+/// property counts as a failure too.
+pub trait Testable {
+    /// `Err` with the reason if the property does not hold.
+    fn result(self) -> Result<(), String>;
+}
+
+impl Testable for bool {
+    fn result(self) -> Result<(), String> {
+        if self {
+            Ok(())
+        } else {
+            Err("returned false".to_string())
+        }
+    }
+}
+
+impl Testable for () {
+    fn result(self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl<E: fmt::Display> Testable for Result<(), E> {
+    fn result(self) -> Result<(), String> {
+        self.map_err(|e| format!("returned error: {}", e))
+    }
+}
+
+/// How a property is checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// How many random inputs to try.
+    pub cases: u32,
+    /// The seed of the inputs; the same seed gives the same inputs.
+    pub seed: u64,
+    /// How many simpler inputs to adopt at most while shrinking.
+    pub max_shrinks: u32,
+}
+
+impl Config {
+    /// 256 cases from the given seed.
+    pub fn with_seed(seed: u64) -> Config {
+        Config {
+            cases: 256,
+            seed,
+            max_shrinks: 1000,
+        }
+    }
+
+    /// 256 cases from the seed in `PROPERTY_SEED` (decimal, or hex with
+    /// `0x`), or from a new seed if it is not set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `PROPERTY_SEED` is set but is not a number.
+    pub fn from_env() -> Config {
+        let seed = match env::var(SEED_VAR) {
+            Ok(value) => parse_seed(&value)
+                .unwrap_or_else(|| panic!("{} is not a seed: {:?}", SEED_VAR, value)),
+            Err(_) => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos() as u64)
+                .unwrap_or(1),
+        };
+        Config::with_seed(seed)
+    }
+}
+
+fn parse_seed(value: &str) -> Option<u64> {
+    let value = value.trim();
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// A failed property: the input it failed on, and a smallest one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure<T> {
+    /// The seed of the run, which replays it.
+    pub seed: u64,
+    /// The number of the case that failed, from 1.
+    pub case: u32,
+    pub original: T,
+    /// The input after shrinking, which still fails.
+    pub minimal: T,
+    pub shrinks: u32,
+    /// Why the property failed on `minimal`.
+    pub reason: String,
+    cases: u32,
+}
+
+impl<T: fmt::Debug> fmt::Display for Failure<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "property failed on case {} of {}; replay with {}={:#x}",
+            self.case, self.cases, SEED_VAR, self.seed
+        )?;
+        writeln!(
+            f,
+            " minimal input: {:?} (after {} shrinks)",
+            self.minimal, self.shrinks
+        )?;
+        writeln!(f, "original input: {:?}", self.original)?;
+        write!(f, "        reason: {}", self.reason)
+    }
+}
+
+// Runs the property on one input, turning a panic into a failure.
+fn run<T, R: Testable>(property: &impl Fn(T) -> R, input: T) -> Result<(), String> {
+    match panic::catch_unwind(AssertUnwindSafe(|| property(input))) {
+        Ok(outcome) => outcome.result(),
+        Err(payload) => Err(match payload.downcast_ref::<&str>() {
+            Some(message) => format!("panicked: {}", message),
+            None => match payload.downcast_ref::<String>() {
+                Some(message) => format!("panicked: {}", message),
+                None => "panicked".to_string(),
+            },
+        }),
+    }
+}
+
+/// Checks `property` on `config.cases` random inputs, and shrinks the
+/// first input it fails on.
+pub fn check<T: Arbitrary, R: Testable>(
+    config: Config,
+    property: impl Fn(T) -> R,
+) -> Result<(), Failure<T>> {
+    let mut rng = Rng::new(config.seed);
+    for case in 0..config.cases {
+        // Start small, where counterexamples are easy to read.
+        let size = (case as usize * 100) / config.cases.max(1) as usize;
+        let input = T::arbitrary(&mut rng, size);
+        let Err(reason) = run(&property, input.clone()) else {
+            continue;
+        };
+
+        let (mut minimal, mut reason, mut shrinks) = (input.clone(), reason, 0);
+        'shrink: while shrinks < config.max_shrinks {
+            for candidate in minimal.shrink() {
+                if let Err(why) = run(&property, candidate.clone()) {
+                    (minimal, reason) = (candidate, why);
+                    shrinks += 1;
+                    continue 'shrink;
+                }
+            }
+            break;
+        }
+        return Err(Failure {
+            seed: config.seed,
+            case: case + 1,
+            original: input,
+            minimal,
+            shrinks,
+            reason,
+            cases: config.cases,
+        });
+    }
+    Ok(())
+}
+
+/// Checks `property` with [`Config::from_env`], and panics with the
+/// [`Failure`] if it does not hold.
+#[track_caller]
+pub fn assert_property<T: Arbitrary, R: Testable>(property: impl Fn(T) -> R) {
+    if let Err(failure) = check(Config::from_env(), property) {
+        panic!("{}", failure);
+    }
+}
+
+/// Runs `case` `config.cases` times with one [`Rng`], for properties whose
+/// inputs are easier to build by hand than through [`Arbitrary`]. A
+/// panicking case is not shrunk, but the panic names the seed that replays
+/// the run.
+#[track_caller]
+pub fn for_each_case(config: Config, mut case: impl FnMut(&mut Rng)) {
+    let mut rng = Rng::new(config.seed);
+    for number in 0..config.cases {
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| case(&mut rng))) {
+            panic!(
+                "case {} of {} failed; replay with {}={:#x}\n{}",
+                number + 1,
+                config.cases,
+                SEED_VAR,
+                config.seed,
+                super::payload_message(&*payload)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minimal<T: Arbitrary, R: Testable>(property: impl Fn(T) -> R) -> T {
+        check(Config::with_seed(0x2545_f491_4f6c_dd1d), property)
+            .unwrap_err()
+            .minimal
+    }
+
+    #[test]
+    fn test_shrinks_to_the_boundary() {
+        assert_eq!(minimal(|x: u32| x < 1000), 1000);
+        assert_eq!(minimal(|x: i64| x > -77), -77);
+        assert_eq!(minimal(|v: Vec<u8>| v.iter().all(|&x| x < 100)), [100]);
+        assert_eq!(minimal(|s: String| !s.contains('b')), "b");
+        // No component can shrink on its own, so the sum is exactly 500.
+        let (a, b) = minimal(|(a, b): (u16, u16)| u32::from(a) + u32::from(b) < 500);
+        assert_eq!(u32::from(a) + u32::from(b), 500);
+    }
+
+    #[test]
+    fn test_panics_fail_with_their_message() {
+        let failure = check(Config::with_seed(3), |(a, b): (i32, i32)| {
+            assert!(a.checked_add(b).is_some(), "{} + {} overflows", a, b);
+        })
+        .unwrap_err();
+        let (a, b) = failure.minimal;
+        assert!(a.checked_add(b).is_none());
+        assert_eq!(failure.reason, format!("panicked: {} + {} overflows", a, b));
+    }
+
+    #[test]
+    fn test_same_seed_same_failure() {
+        let property = |v: Vec<i32>| v.len() < 5 || v[0] <= v[4];
+        let first = check(Config::with_seed(42), property).unwrap_err();
+        assert_eq!(check(Config::with_seed(42), property), Err(first.clone()));
+        assert_eq!(first.minimal.len(), 5);
+        assert!(first.to_string().starts_with(&format!(
+            "property failed on case {} of 256; replay with PROPERTY_SEED=0x2a\n",
+            first.case
+        )));
+    }
+
+    #[test]
+    fn test_for_each_case_names_the_seed() {
+        let mut calls = 0;
+        for_each_case(Config::with_seed(5), |_| calls += 1);
+        assert_eq!(calls, 256);
+
+        let message = crate::testing::panic_message(|| {
+            for_each_case(Config::with_seed(5), |rng| {
+                assert!(rng.below(10) < 9, "too big")
+            })
+        });
+        let message = message.unwrap();
+        assert!(
+            message.starts_with("case ")
+                && message.ends_with("; replay with PROPERTY_SEED=0x5\ntoo big"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_passing_property() {
+        assert_eq!(
+            check(Config::with_seed(1), |x: i8| i16::from(x) * 2 % 2 == 0),
+            Ok(())
+        );
+        assert_eq!(parse_seed("0x2A"), Some(42));
+        assert_eq!(parse_seed("42"), Some(42));
+        assert_eq!(parse_seed("forty-two"), None);
+    }
+}
@@ -0,0 +1,84 @@
+// With the count-allocations feature, the library installs the counting
+// allocator itself, and a second one would not compile.
+use rust_by_example::allocations::{self, measure};
+use std::{mem, thread};
+
+#[cfg(not(feature = "count-allocations"))]
+#[global_allocator]
+static ALLOCATOR: allocations::CountingAllocator = allocations::CountingAllocator;
+
+// As in box_stack_and_heap of 19_std_library_types.rs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+fn origin() -> Point {
+    Point { x: 0.0, y: 0.0 }
+}
+
+#[test]
+fn test_box_allocates_once() {
+    let (boxed, stats) = measure(|| Box::new(origin()));
+    assert_eq!(stats.allocations, 1);
+    assert_eq!(stats.bytes_allocated, mem::size_of::<Point>() as u64);
+    assert_eq!(stats.deallocations, 0);
+
+    let ((), stats) = measure(|| drop(boxed));
+    assert_eq!((stats.deallocations, stats.net_bytes()), (1, -16));
+
+    let (_, stats) = measure(|| Box::new(Box::new(origin())));
+    assert_eq!(stats.allocations, 2);
+}
+
+#[test]
+fn test_point_copies_allocate_nothing() {
+    let boxed = Box::new(origin());
+    let (points, stats) = measure(|| {
+        let point = origin();
+        let copy = point;
+        let unboxed: Point = *boxed;
+        [point, copy, unboxed]
+    });
+    assert_eq!(points, [origin(); 3]);
+    assert_eq!(stats, allocations::Stats::default());
+}
+
+#[test]
+fn test_growing_a_vec_reallocates() {
+    let (_, stats) = measure(|| {
+        let mut v = Vec::new();
+        for i in 0..100u32 {
+            v.push(i);
+        }
+        v
+    });
+    assert_eq!(stats.allocations, 1);
+    assert!(stats.reallocations > 1);
+    assert_eq!(
+        stats.net_bytes(),
+        stats.bytes_allocated as i64 - stats.bytes_deallocated as i64
+    );
+
+    let (_, stats) = measure(|| {
+        let mut v = Vec::with_capacity(100);
+        v.extend(0..100u32);
+        v
+    });
+    assert_eq!((stats.allocations, stats.reallocations), (1, 0));
+    assert_eq!(stats.bytes_allocated, 400);
+}
+
+#[test]
+fn test_counts_are_per_thread() {
+    let (inner, outer) = measure(|| {
+        thread::spawn(|| measure(|| vec![0u8; 1000]).1)
+            .join()
+            .unwrap()
+    });
+    assert_eq!((inner.allocations, inner.bytes_allocated), (1, 1000));
+    // Spawning the thread allocates a little here, but the vector belongs
+    // to the other thread.
+    assert!(outer.bytes_allocated < 1000, "{:?}", outer);
+}
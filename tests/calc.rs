@@ -0,0 +1,40 @@
+mod common;
+
+#[test]
+fn test_calc_session() {
+    let fixture = common::setup();
+    let input = "1 + 2 * 3\nx = ans - 1\n\nx ^ 2 % 7\nvars\n";
+    let output = fixture.run("calc", &[], input);
+    assert!(output.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "7\nx = 6\n1\nans = 1\nx = 6\n");
+}
+
+#[test]
+fn test_calc_errors_point_into_the_line() {
+    let fixture = common::setup();
+    let output = fixture.run("calc", &[], "y = 10 / (3 - 3)\n2 *\nquit\n1\n");
+    assert!(output.success());
+    assert_eq!(output.stdout, "");
+    assert_eq!(
+        output.stderr,
+        "y = 10 / (3 - 3)\n    ^^^^^^^^^^^^\nerror: division by zero\n\
+         2 *\n   ^\nerror: unexpected end of input\n"
+    );
+}
+
+#[test]
+fn test_calc_survives_hostile_input() {
+    let fixture = common::setup();
+    let mut input = b"-2147483648\n\xff\xfe\n".to_vec();
+    input.extend("-".repeat(200_000).as_bytes());
+    input.extend(b"1\nans\n");
+    let output = fixture.run("calc", &[], input);
+    assert!(output.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "-2147483648\n-2147483648\n");
+    assert!(output
+        .stderr
+        .starts_with("error: the line is not valid UTF-8\n"));
+    assert!(output
+        .stderr
+        .ends_with("error: nested more than 256 deep\n"));
+}
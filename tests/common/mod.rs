@@ -6,7 +6,267 @@
 // because the test runner will treat the file as a test crate and try to
 // run tests inside it.
 
-pub fn setup() {
-    // some setup code, like creating required files/directories, starting
-    // servers, etc.
+// Every test crate uses a different part of this module.
+#![allow(dead_code)]
+
+use std::{
+    env, fs,
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+/// A directory of its own, removed with everything in it when dropped.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new() -> TempDir {
+        // The process id keeps test crates apart, the counter keeps tests
+        // within one crate apart.
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = env::temp_dir().join(format!(
+            "rust-by-example-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::SeqCst)
+        ));
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        // Failing to clean up must not hide the result of the test.
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+pub fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// A private copy of the repository's `data/` directory, laid out like the
+/// repository so that the examples' `../../data/...` paths lead to it.
+pub struct Fixture {
+    dir: TempDir,
+}
+
+/// Sets up a fixture for one test.
+pub fn setup() -> Fixture {
+    let dir = TempDir::new();
+    copy_dir(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("data"),
+        &dir.path().join("data"),
+    )
+    .unwrap();
+    fs::create_dir_all(dir.path().join("src/bin")).unwrap();
+    Fixture { dir }
+}
+
+/// What a binary printed, and how it exited.
+#[derive(Debug)]
+pub struct Output {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Output {
+    pub fn success(&self) -> bool {
+        self.status.success()
+    }
+
+    pub fn code(&self) -> Option<i32> {
+        self.status.code()
+    }
+}
+
+/// Where cargo built the binaries of the crate, which all go to the same
+/// directory.
+pub fn bin_dir() -> PathBuf {
+    Path::new(env!("CARGO_BIN_EXE_rust_by_example"))
+        .parent()
+        .unwrap()
+        .to_path_buf()
+}
+
+fn bin_path(name: &str) -> PathBuf {
+    bin_dir().join(format!("{}{}", name, env::consts::EXE_SUFFIX))
+}
+
+impl Fixture {
+    /// The root of the fixture, which holds `data/` and `src/bin/`.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn data(&self) -> PathBuf {
+        self.path().join("data")
+    }
+
+    /// The `src/bin/` of the fixture, where binaries run so that their
+    /// `../../data` paths lead to [`data`](Fixture::data).
+    pub fn work_dir(&self) -> PathBuf {
+        self.path().join("src/bin")
+    }
+
+    /// Runs the binary `name` from `src/bin/` of the fixture with `args`,
+    /// feeding it `stdin`.
+    pub fn run(&self, name: &str, args: &[&str], stdin: impl AsRef<[u8]>) -> Output {
+        let mut child = Command::new(bin_path(name))
+            .args(args)
+            .current_dir(self.work_dir())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|e| panic!("could not run {}: {}", name, e));
+        // Write from another thread, so a binary that prints before it
+        // reads everything cannot deadlock with us.
+        let mut pipe = child.stdin.take().unwrap();
+        let input = stdin.as_ref().to_vec();
+        let writer = thread::spawn(move || {
+            // The binary may exit without reading all of it.
+            let _ = pipe.write_all(&input);
+        });
+        let output = child.wait_with_output().unwrap();
+        writer.join().unwrap();
+        Output {
+            status: output.status,
+            stdout: String::from_utf8(output.stdout).unwrap(),
+            stderr: String::from_utf8(output.stderr).unwrap(),
+        }
+    }
+}
+
+/// A line-based TCP server on a free loopback port, answering each line
+/// it receives with `respond(line)`. It stops when dropped.
+pub struct Server {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<String>>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+    clients: Arc<Mutex<Vec<Client>>>,
+}
+
+// A connection, and the thread that serves it.
+struct Client {
+    stream: TcpStream,
+    thread: thread::JoinHandle<()>,
+}
+
+fn serve<F: Fn(&str) -> String>(
+    stream: TcpStream,
+    respond: &F,
+    requests: &Mutex<Vec<String>>,
+) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        requests.lock().unwrap().push(line.clone());
+        writeln!(writer, "{}", respond(&line))?;
+    }
+    Ok(())
+}
+
+impl Server {
+    pub fn start<F: Fn(&str) -> String + Send + Sync + 'static>(respond: F) -> Server {
+        // Port 0 lets the system pick a port no other test is using.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let clients = Arc::new(Mutex::new(Vec::new()));
+
+        let thread = {
+            let requests = Arc::clone(&requests);
+            let stop = Arc::clone(&stop);
+            let clients = Arc::clone(&clients);
+            let respond = Arc::new(respond);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else { continue };
+                    // Kept so that dropping the server can hang up on it.
+                    let Ok(handle) = stream.try_clone() else {
+                        continue;
+                    };
+                    let respond = Arc::clone(&respond);
+                    let requests = Arc::clone(&requests);
+                    // One thread per client, so that an idle one blocks nobody.
+                    let thread = thread::spawn(move || {
+                        // A client hanging up early is not the server's problem.
+                        let _ = serve(stream, &*respond, &requests);
+                    });
+                    clients.lock().unwrap().push(Client {
+                        stream: handle,
+                        thread,
+                    });
+                }
+            })
+        };
+        Server {
+            addr,
+            requests,
+            stop,
+            thread: Some(thread),
+            clients,
+        }
+    }
+
+    /// A server that sends every line back.
+    pub fn echo() -> Server {
+        Server::start(|line| line.to_string())
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Every line received so far, across all connections.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake the accept loop up so that it sees the flag.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        // Hang up on clients that are still connected, which ends their
+        // threads.
+        let clients = std::mem::take(&mut *self.clients.lock().unwrap());
+        for client in clients {
+            let _ = client.stream.shutdown(Shutdown::Both);
+            let _ = client.thread.join();
+        }
+    }
 }
@@ -0,0 +1,281 @@
+// Checks the claims of the commented-out "Error!" lines in src/bin/.
+//
+// Several examples keep a line that does not compile, commented out under
+// a note like `// Error! `private_function` is private`. For every such
+// line, this test uncomments it in a copy of the example, compiles the copy
+// with the local `rustc` (or `$RUSTC`), and checks that compilation fails
+// with the error code listed in `EXPECTED` below.
+//
+// A marker is a comment containing `Error!` or `Error:`. The commented-out
+// lines ending in `;` that directly follow it are each checked on their
+// own. Only single-file examples are scanned.
+
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+/// (example file, commented-out code, expected error code)
+const EXPECTED: [(&str, &str, &str); 12] = [
+    ("05_types.rs", "let integer: u8 = decimal;", "E0308"),
+    ("05_types.rs", "let character = decimal as char;", "E0604"),
+    (
+        "10_modules_struct_visibility.rs",
+        r#"let closed_box = my::ClosedBox { contents: "classified information" };"#,
+        "E0451",
+    ),
+    (
+        "10_modules_struct_visibility.rs",
+        r#"println!("The closed box contains: {}", _closed_box.contents);"#,
+        "E0616",
+    ),
+    (
+        "10_modules_visibility.rs",
+        "my_mod::nested::public_function_in_my_mod();",
+        "E0603",
+    ),
+    (
+        "10_modules_visibility.rs",
+        "my_mod::private_function();",
+        "E0603",
+    ),
+    (
+        "10_modules_visibility.rs",
+        "my_mod::nested::private_function();",
+        "E0603",
+    ),
+    (
+        "10_modules_visibility.rs",
+        "my_mod::private_nested::function();",
+        "E0603",
+    ),
+    (
+        "10_modules_visibility.rs",
+        "my_mod::private_nested::restricted_function();",
+        "E0603",
+    ),
+    (
+        "14_generics_9_phantom_type_parameters.rs",
+        r#"println!("_tuple1 == _tuple2 yields: {}", _tuple1 == _tuple2);"#,
+        "E0308",
+    ),
+    (
+        "14_generics_9_phantom_type_parameters.rs",
+        r#"println!("_struct1 == _struct2 yields: {}", _struct1 == _struct2);"#,
+        "E0308",
+    ),
+    (
+        "14_generics_9_phantom_type_parameters_1.rs",
+        "let one_feter = one_foot + one_meter;",
+        "E0308",
+    ),
+];
+
+/// A commented-out line claimed not to compile.
+#[derive(Debug)]
+struct Case {
+    file: String,
+    /// The index of the line in the file.
+    index: usize,
+    code: String,
+}
+
+fn is_marker(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("//") && (line.contains("Error!") || line.contains("Error:"))
+}
+
+fn find_cases(file: &str, source: &str) -> Vec<Case> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut cases = Vec::new();
+    for (marker, _) in lines.iter().enumerate().filter(|(_, line)| is_marker(line)) {
+        let comments = lines[marker + 1..]
+            .iter()
+            .take_while(|line| line.trim_start().starts_with("//"));
+        for (offset, line) in comments.enumerate() {
+            let code = line.trim_start().trim_start_matches('/').trim();
+            if code.ends_with(';') {
+                cases.push(Case {
+                    file: file.to_string(),
+                    index: marker + 1 + offset,
+                    code: code.to_string(),
+                });
+            }
+        }
+    }
+    cases
+}
+
+fn uncomment(source: &str, index: usize) -> String {
+    source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == index {
+                format!("{}\n", line.replacen("//", "", 1))
+            } else {
+                format!("{}\n", line)
+            }
+        })
+        .collect()
+}
+
+fn rustc() -> OsString {
+    env::var_os("RUSTC").unwrap_or_else(|| "rustc".into())
+}
+
+// A directory of its own for every compilation, so they can run at once.
+fn scratch_dir() -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let dir = env::temp_dir().join(format!(
+        "rbe-compile-fail-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::SeqCst)
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Compiles `source` and returns the compiler's error output, or `None` if
+/// it compiled.
+fn compile(name: &str, source: &str) -> Option<String> {
+    let dir = scratch_dir();
+    let path = dir.join(name);
+    fs::write(&path, source).unwrap();
+    // Type checking is enough; skip code generation.
+    let output = Command::new(rustc())
+        .args([
+            "--edition",
+            "2021",
+            "--crate-type",
+            "bin",
+            "--emit",
+            "metadata",
+        ])
+        .arg("--out-dir")
+        .arg(&dir)
+        .arg(&path)
+        .output()
+        .expect("could not run rustc");
+    fs::remove_dir_all(&dir).unwrap();
+    if output.status.success() {
+        None
+    } else {
+        Some(String::from_utf8_lossy(&output.stderr).into_owned())
+    }
+}
+
+fn check(case: &Case, source: &str) -> Result<(), String> {
+    let Some((_, _, expected)) = EXPECTED
+        .iter()
+        .find(|(file, code, _)| *file == case.file && *code == case.code)
+    else {
+        return Err(format!(
+            "{}:{}: no expected error code for `{}`; add it to EXPECTED",
+            case.file,
+            case.index + 1,
+            case.code
+        ));
+    };
+    match compile(&case.file, &uncomment(source, case.index)) {
+        None => Err(format!(
+            "{}:{}: `{}` compiles, but is marked as an error",
+            case.file,
+            case.index + 1,
+            case.code
+        )),
+        Some(errors) if errors.contains(&format!("error[{}]", expected)) => Ok(()),
+        Some(errors) => Err(format!(
+            "{}:{}: `{}` fails without error {}:\n{}",
+            case.file,
+            case.index + 1,
+            case.code,
+            expected,
+            errors
+        )),
+    }
+}
+
+#[test]
+fn test_error_lines_fail_to_compile() {
+    let bin = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/bin");
+    let mut sources = Vec::new();
+    for entry in fs::read_dir(&bin).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|e| e == "rs") {
+            let file = path.file_name().unwrap().to_str().unwrap().to_string();
+            sources.push((file, fs::read_to_string(&path).unwrap()));
+        }
+    }
+
+    let cases: Vec<(Case, &str)> = sources
+        .iter()
+        .flat_map(|(file, source)| {
+            find_cases(file, source)
+                .into_iter()
+                .map(move |case| (case, source.as_str()))
+        })
+        .collect();
+    let mut failures: Vec<String> = thread::scope(|scope| {
+        let checks: Vec<_> = cases
+            .iter()
+            .map(|(case, source)| scope.spawn(move || check(case, source)))
+            .collect();
+        checks
+            .into_iter()
+            .filter_map(|check| check.join().unwrap().err())
+            .collect()
+    });
+
+    // An expectation whose line is gone checks nothing.
+    for (file, code, _) in EXPECTED {
+        if !cases
+            .iter()
+            .any(|(case, _)| case.file == file && case.code == code)
+        {
+            failures.push(format!("{}: no marked line `{}`", file, code));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn test_unmodified_examples_compile() {
+    // Otherwise the failures above might have nothing to do with the lines.
+    let bin = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/bin");
+    let mut files: Vec<&str> = EXPECTED.iter().map(|(file, _, _)| *file).collect();
+    files.dedup();
+    thread::scope(|scope| {
+        for file in files {
+            let bin = &bin;
+            scope.spawn(move || {
+                let source = fs::read_to_string(bin.join(file)).unwrap();
+                if let Some(errors) = compile(file, &source) {
+                    panic!("{} does not compile:\n{}", file, errors);
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn test_find_cases() {
+    let source = "fn main() {\n    \
+                  // Error! `x` is private\n    \
+                  // Some explanation\n    \
+                  // a::x();\n    \
+                  // a::y();\n    \
+                  // TODO ^ Try uncommenting these lines\n\n    \
+                  // b::z();\n\
+                  }\n";
+    let cases = find_cases("demo.rs", source);
+    let found: Vec<(usize, &str)> = cases.iter().map(|c| (c.index, c.code.as_str())).collect();
+    assert_eq!(found, [(3, "a::x();"), (4, "a::y();")]);
+    assert!(uncomment(source, 3).contains("\n     a::x();\n"));
+}
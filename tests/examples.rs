@@ -0,0 +1,60 @@
+mod common;
+
+use std::process::Command;
+
+// The main binary, running the examples in a fixture of their own.
+fn rust_by_example(fixture: &common::Fixture) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rust_by_example"));
+    command.env("RUST_BY_EXAMPLE_RUN_DIR", fixture.work_dir());
+    command
+}
+
+#[test]
+fn test_runs_one_example_by_name() {
+    let fixture = common::setup();
+    let output = rust_by_example(&fixture)
+        .arg("01_hello_world_2")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[0: 1, 1: 2, 2: 3]\n"
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .ends_with("[01_hello_world_2: exit code 0]\n"));
+}
+
+#[test]
+fn test_unknown_example_fails() {
+    let fixture = common::setup();
+    let output = rust_by_example(&fixture)
+        .arg("99_nothing")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "error: no example called `99_nothing`\n"
+    );
+}
+
+#[test]
+fn test_all_passes_and_skips_tagged_examples() {
+    let fixture = common::setup();
+    std::fs::remove_file(fixture.data().join("lorem_ipsum.txt")).unwrap();
+    let output = rust_by_example(&fixture).arg("--all").output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success(), "{}", stdout);
+    let skipped = stdout
+        .lines()
+        .find(|line| line.contains("20_std_misc_5_child_processes"))
+        .unwrap();
+    assert!(skipped.contains("skip") && skipped.ends_with("slow"));
+    let skipped = stdout.lines().find(|line| line.contains("calc")).unwrap();
+    assert!(skipped.contains("skip") && skipped.ends_with("interactive"));
+    assert!(stdout.ends_with(" passed, 0 failed, 2 skipped\n"));
+    // 20_std_misc_4_file_io wrote to the fixture, not the repository.
+    assert!(fixture.data().join("lorem_ipsum.txt").is_file());
+}
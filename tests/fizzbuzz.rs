@@ -0,0 +1,36 @@
+use rust_by_example::fizzbuzz::Rules;
+use std::process::Command;
+
+// The output of `while_loop` in 08_flow_of_control.rs.
+const GOLDEN: &str = include_str!("golden/fizzbuzz_1_100.txt");
+
+#[test]
+fn test_classic_preset_matches_while_loop() {
+    let output: Vec<String> = Rules::classic().apply(1..=100).collect();
+    assert_eq!(output, GOLDEN.lines().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_rules_file_matches_preset() {
+    let rules = Rules::load("data/fizzbuzz.txt").unwrap();
+    assert!(rules.apply(1..=100).eq(Rules::classic().apply(1..=100)));
+}
+
+#[test]
+fn test_binary_prints_and_counts() {
+    let output = Command::new(env!("CARGO_BIN_EXE_fizzbuzz"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), GOLDEN);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fizzbuzz"))
+        .args(["--rules", "data/fizzbuzz.txt", "--count"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "buzz: 14\nfizz: 27\nfizzbuzz: 6\nnumbers: 53\n"
+    );
+}
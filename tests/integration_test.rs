@@ -1,9 +1,81 @@
 // importing common module.
 mod common;
 
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+};
+
 #[test]
 fn test_add() {
     // using common code.
-    common::setup();
+    let _fixture = common::setup();
     assert_eq!(rust_by_example::add(3, 2), 5);
 }
+
+#[test]
+fn test_fixtures_are_private_and_cleaned_up() {
+    let first = common::setup();
+    let second = common::setup();
+    assert_ne!(first.path(), second.path());
+    assert!(first.data().join("hello.txt").is_file());
+
+    // 20_std_misc_4_file_io writes data/lorem_ipsum.txt; only this fixture's
+    // copy may change.
+    fs::remove_file(first.data().join("lorem_ipsum.txt")).unwrap();
+    let output = first.run("20_std_misc_4_file_io", &[], "");
+    assert!(output.success(), "{}", output.stderr);
+    assert!(first.data().join("lorem_ipsum.txt").is_file());
+
+    let path = first.path().to_path_buf();
+    drop(first);
+    assert!(!path.exists());
+    assert!(second.data().join("lorem_ipsum.txt").is_file());
+}
+
+#[test]
+fn test_run_reports_output_and_status() {
+    let fixture = common::setup();
+    let output = fixture.run(
+        "fizzbuzz",
+        &["--rules", "../../data/fizzbuzz.txt", "9", "10"],
+        "",
+    );
+    assert_eq!(
+        (output.code(), output.stdout.as_str()),
+        (Some(0), "fizz\nbuzz\n")
+    );
+
+    let output = fixture.run("fizzbuzz", &["--bogus"], "ignored input");
+    assert_eq!(output.code(), Some(2));
+    assert!(output
+        .stderr
+        .starts_with("error: unexpected argument `--bogus`"));
+}
+
+#[test]
+fn test_loopback_server() {
+    let server = common::Server::start(|line| line.to_uppercase());
+    for word in ["ping", "pong"] {
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        writeln!(stream, "{}", word).unwrap();
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply).unwrap();
+        assert_eq!(reply, format!("{}\n", word.to_uppercase()));
+    }
+    assert_eq!(server.requests(), ["ping", "pong"]);
+
+    let echo = common::Server::echo();
+    assert_ne!(echo.addr(), server.addr());
+
+    // Dropping the server hangs up on a client that is still connected.
+    let mut idle = TcpStream::connect(echo.addr()).unwrap();
+    writeln!(idle, "hello").unwrap();
+    let mut reader = BufReader::new(idle.try_clone().unwrap());
+    let mut reply = String::new();
+    reader.read_line(&mut reply).unwrap();
+    drop(echo);
+    reply.clear();
+    assert_eq!(reader.read_line(&mut reply).unwrap_or(0), 0);
+}
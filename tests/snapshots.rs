@@ -0,0 +1,267 @@
+// Runs every example in src/bin/ and compares what it prints with the
+// snapshot in tests/snapshots/<example>.snap.
+//
+// After an intended change in output, re-bless the snapshots with
+//
+//     BLESS=1 cargo test --test snapshots
+//
+// and review the changes to tests/snapshots/ like any other diff.
+
+mod common;
+
+use rust_by_example::examples::{Example, ExampleIndex, Outcome};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    thread,
+};
+
+fn snapshot_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots")
+}
+
+fn blessing() -> bool {
+    env::var_os("BLESS").is_some_and(|value| value != "0")
+}
+
+// Sorts the lines that start with `prefix` among themselves, leaving every
+// other line in place. For output from threads that may finish in any order.
+fn sort_lines_starting_with(text: &str, prefix: &str) -> String {
+    let mut lines: Vec<&str> = text.lines().collect();
+    let positions: Vec<usize> = (0..lines.len())
+        .filter(|&i| lines[i].starts_with(prefix))
+        .collect();
+    let mut matching: Vec<&str> = positions.iter().map(|&i| lines[i]).collect();
+    matching.sort();
+    for (&i, line) in positions.iter().zip(matching) {
+        lines[i] = line;
+    }
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+// Makes output that legitimately differs between runs or platforms
+// comparable. Examples without an entry are compared as they are.
+fn normalize(example: &str, stdout: &str) -> String {
+    match example {
+        // Every line is printed by a thread and says which one.
+        "20_std_misc_1_threads" | "20_std_misc_1_threads_testcase" => {
+            let mut lines: Vec<&str> = stdout.lines().collect();
+            lines.sort();
+            lines.iter().map(|line| format!("{}\n", line)).collect()
+        }
+        // The ids arrive in the order the threads finish.
+        "20_std_misc_2_channels" => sort_lines_starting_with(stdout, "thread ")
+            .lines()
+            .map(
+                |line| match line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                    Some(ids) => {
+                        let mut ids: Vec<&str> = ids.split(", ").collect();
+                        ids.sort();
+                        format!("[{}]\n", ids.join(", "))
+                    }
+                    None => format!("{}\n", line),
+                },
+            )
+            .collect(),
+        // Only printed with the count-allocations feature.
+        "19_std_library_types" => stdout
+            .lines()
+            .filter(|line| !line.starts_with("Allocations by "))
+            .map(|line| format!("{}\n", line))
+            .collect(),
+        // `wc` pads its counts differently on every platform.
+        "20_std_misc_5_child_processes" => stdout
+            .lines()
+            .map(|line| {
+                format!(
+                    "{}\n",
+                    line.split_whitespace().collect::<Vec<_>>().join(" ")
+                )
+            })
+            .collect(),
+        _ => stdout.to_string(),
+    }
+}
+
+fn render(example: &Example, outcome: &Outcome) -> String {
+    format!(
+        "status: {}\n--- stdout\n{}",
+        outcome.status,
+        normalize(&example.name, &outcome.stdout)
+    )
+}
+
+// A line diff of `expected` against `actual`, from a longest common
+// subsequence, with up to two lines of context around each change.
+fn diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+    // lcs[i][j] is the length of the longest common subsequence of
+    // old[i..] and new[j..].
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(('-', old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
+    }
+
+    const CONTEXT: usize = 2;
+    let changed: Vec<usize> = (0..lines.len()).filter(|&k| lines[k].0 != ' ').collect();
+    let mut output = String::new();
+    let mut last_shown = None;
+    for (k, (sign, line)) in lines.iter().enumerate() {
+        let near_change = changed.iter().any(|&c| c.abs_diff(k) <= CONTEXT);
+        if !near_change {
+            continue;
+        }
+        if last_shown.is_some_and(|last| last + 1 < k) {
+            output.push_str("  ...\n");
+        }
+        output.push_str(&format!("{} {}\n", sign, line));
+        last_shown = Some(k);
+    }
+    output
+}
+
+// The variables that choose the language of messages. The snapshots are in
+// English, whatever the locale of the machine running the tests.
+const LOCALE_VARIABLES: [&str; 3] = ["LANG", "LC_ALL", "LC_MESSAGES"];
+
+/// Compares one example with its snapshot, or writes the snapshot when
+/// blessing. The example runs in a fixture of its own, and `inherited` is
+/// added to the environment it inherits. Returns a description of the
+/// mismatch, if any.
+fn check(example: &Example, inherited: &[(&str, &str)]) -> Option<String> {
+    let fixture = common::setup();
+    let outcome = example.run_with(&common::bin_dir(), &[], None, |command| {
+        command.current_dir(fixture.work_dir());
+        command.envs(inherited.iter().copied());
+        for variable in LOCALE_VARIABLES {
+            command.env_remove(variable);
+        }
+    });
+    let outcome = match outcome {
+        Ok(outcome) => outcome,
+        Err(e) => return Some(format!("{}: could not run: {}", example.name, e)),
+    };
+    let actual = render(example, &outcome);
+    let path = snapshot_dir().join(format!("{}.snap", example.name));
+    if blessing() {
+        fs::write(&path, actual).unwrap();
+        return None;
+    }
+    match fs::read_to_string(&path) {
+        Ok(expected) if expected == actual => None,
+        Ok(expected) => Some(format!(
+            "{}: output differs from {} (- snapshot, + actual):\n{}",
+            example.name,
+            path.display(),
+            diff(&expected, &actual)
+        )),
+        Err(_) => Some(format!(
+            "{}: no snapshot at {}; run with BLESS=1 to create it",
+            example.name,
+            path.display()
+        )),
+    }
+}
+
+// Whether the snapshot of an example holds on this target. The sizes that
+// `layout` prints are those of x86_64 Linux.
+fn runs_here(example: &str) -> bool {
+    example != "layout" || cfg!(all(target_arch = "x86_64", target_os = "linux"))
+}
+
+#[test]
+fn test_examples_match_snapshots() {
+    let index = ExampleIndex::discover(ExampleIndex::DEFAULT_DIR).unwrap();
+    fs::create_dir_all(snapshot_dir()).unwrap();
+
+    // Running the examples in parallel hides the slow ones.
+    let mut failures: Vec<String> = thread::scope(|scope| {
+        let checks: Vec<_> = index
+            .examples()
+            .iter()
+            .filter(|example| runs_here(&example.name))
+            .map(|example| scope.spawn(move || check(example, &[])))
+            .collect();
+        checks
+            .into_iter()
+            .filter_map(|check| check.join().unwrap())
+            .collect()
+    });
+
+    // A snapshot without an example is left over from a rename or removal.
+    for entry in fs::read_dir(snapshot_dir()).unwrap() {
+        let path = entry.unwrap().path();
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        if index.examples().iter().all(|example| example.name != name) {
+            if blessing() {
+                fs::remove_file(&path).unwrap();
+            } else {
+                failures.push(format!(
+                    "{}: snapshot for an unknown example",
+                    path.display()
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn test_snapshots_do_not_depend_on_the_locale() {
+    let index = ExampleIndex::discover(ExampleIndex::DEFAULT_DIR).unwrap();
+    // The example that greets in the language of the locale.
+    let example = index.find("24_meta_documentation").unwrap();
+    let german = [("LANG", "de_DE.UTF-8"), ("LC_ALL", "de_DE.UTF-8")];
+    assert_eq!(check(example, &german), None);
+}
+
+#[test]
+fn test_diff_shows_changes_with_context() {
+    let expected = "a\nb\nc\nd\ne\nf\ng\n";
+    let actual = "a\nb\nc\nD\ne\nf\ng\nh\n";
+    assert_eq!(
+        diff(expected, actual),
+        "  b\n  c\n- d\n+ D\n  e\n  f\n  g\n+ h\n"
+    );
+    assert_eq!(diff("same\n", "same\n"), "");
+}
+
+#[test]
+fn test_normalizers_make_thread_output_stable() {
+    let one = "thread 2 finished\nthread 0 finished\n[Ok(2), Ok(0)]\n";
+    let other = "thread 0 finished\nthread 2 finished\n[Ok(0), Ok(2)]\n";
+    assert_eq!(
+        normalize("20_std_misc_2_channels", one),
+        normalize("20_std_misc_2_channels", other)
+    );
+    assert_eq!(
+        sort_lines_starting_with("x\nb 2\ny\nb 1\n", "b "),
+        "x\nb 1\ny\nb 2\n"
+    );
+}